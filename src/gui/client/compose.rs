@@ -1,8 +1,9 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::{Context, Result};
+use tonic::Streaming;
 
-use crate::proto::DeployRequest;
+use crate::proto::{DeployReply, DeployRequest};
 use crate::state::compose::ComposeFileDiff;
 
 use crate::proto::{self, compose_client::ComposeClient, ComposeFile, DiffRequest, PushRequest};
@@ -43,12 +44,32 @@ pub async fn push_file(server_address: String, file_diff: ComposeFileDiff) -> Re
     Ok(())
 }
 
-pub async fn deploy(server_address: String, path: PathBuf) -> Result<()> {
+pub async fn deploy(
+    server_address: String,
+    path: PathBuf,
+    variables: HashMap<String, String>,
+) -> Result<Streaming<DeployReply>> {
     let mut client = ComposeClient::connect(server_address).await?;
     let request = tonic::Request::new(DeployRequest {
         path: path.to_str().context("invalid path {path:?}")?.to_string(),
+        variables,
     });
 
-    client.deploy(request).await?;
-    Ok(())
+    let stream = client.deploy(request).await?.into_inner();
+    Ok(stream)
+}
+
+pub async fn undeploy(
+    server_address: String,
+    path: PathBuf,
+    variables: HashMap<String, String>,
+) -> Result<Streaming<DeployReply>> {
+    let mut client = ComposeClient::connect(server_address).await?;
+    let request = tonic::Request::new(DeployRequest {
+        path: path.to_str().context("invalid path {path:?}")?.to_string(),
+        variables,
+    });
+
+    let stream = client.undeploy(request).await?.into_inner();
+    Ok(stream)
 }
@@ -1,6 +1,16 @@
-use crate::proto::{docker_client::DockerClient, ContainerIdentifier};
+use crate::proto::{
+    docker_client::DockerClient, upload_chunk, ArchiveChunk, ArchiveRequest, CommitRequest,
+    ContainerIdentifier, DockerEvent, Empty, ExecInput, ExecOutput, FilesystemChanges,
+    KillContainerRequest, ProcessList, RemoveContainerRequest, RenameContainerRequest,
+    StopContainerRequest, TopRequest, UploadChunk,
+};
 
 use anyhow::Result;
+use tokio::sync::mpsc::Receiver;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Streaming;
+
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
 
 pub async fn start_container(id: String, server_address: String) -> Result<()> {
     let mut client = DockerClient::connect(server_address).await?;
@@ -10,18 +20,158 @@ pub async fn start_container(id: String, server_address: String) -> Result<()> {
     Ok(())
 }
 
-pub async fn stop_container(id: String, server_address: String) -> Result<()> {
+pub async fn stop_container(
+    id: String,
+    timeout: Option<u32>,
+    server_address: String,
+) -> Result<()> {
     let mut client = DockerClient::connect(server_address).await?;
-    let request = tonic::Request::new(ContainerIdentifier { id });
+    let request = tonic::Request::new(StopContainerRequest { id, timeout });
     client.stop_container(request).await?;
 
     Ok(())
 }
 
-pub async fn remove_container(id: String, server_address: String) -> Result<()> {
+pub async fn restart_container(
+    id: String,
+    timeout: Option<u32>,
+    server_address: String,
+) -> Result<()> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(StopContainerRequest { id, timeout });
+    client.restart_container(request).await?;
+
+    Ok(())
+}
+
+pub async fn pause_container(id: String, server_address: String) -> Result<()> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(ContainerIdentifier { id });
+    client.pause_container(request).await?;
+
+    Ok(())
+}
+
+pub async fn unpause_container(id: String, server_address: String) -> Result<()> {
     let mut client = DockerClient::connect(server_address).await?;
     let request = tonic::Request::new(ContainerIdentifier { id });
+    client.unpause_container(request).await?;
+
+    Ok(())
+}
+
+pub async fn kill_container(id: String, signal: String, server_address: String) -> Result<()> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(KillContainerRequest { id, signal });
+    client.kill_container(request).await?;
+
+    Ok(())
+}
+
+pub async fn rename_container(id: String, name: String, server_address: String) -> Result<()> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(RenameContainerRequest { id, name });
+    client.rename_container(request).await?;
+
+    Ok(())
+}
+
+pub async fn remove_container(
+    id: String,
+    force: bool,
+    remove_volumes: bool,
+    server_address: String,
+) -> Result<()> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(RemoveContainerRequest {
+        id,
+        force,
+        remove_volumes,
+    });
     client.remove_container(request).await?;
 
     Ok(())
 }
+
+pub async fn exec(
+    input: Receiver<ExecInput>,
+    server_address: String,
+) -> Result<Streaming<ExecOutput>> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(ReceiverStream::new(input));
+    let stream = client.exec(request).await?.into_inner();
+
+    Ok(stream)
+}
+
+pub async fn download_archive(
+    id: String,
+    path: String,
+    server_address: String,
+) -> Result<Streaming<ArchiveChunk>> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(ArchiveRequest { id, path });
+    let stream = client.download_archive(request).await?.into_inner();
+
+    Ok(stream)
+}
+
+pub async fn upload_archive(
+    id: String,
+    path: String,
+    archive: Vec<u8>,
+    server_address: String,
+) -> Result<()> {
+    let mut chunks = vec![UploadChunk {
+        chunk: Some(upload_chunk::Chunk::Target(ArchiveRequest { id, path })),
+    }];
+    chunks.extend(archive.chunks(ARCHIVE_CHUNK_SIZE).map(|data| UploadChunk {
+        chunk: Some(upload_chunk::Chunk::Data(data.to_vec())),
+    }));
+
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(tokio_stream::iter(chunks));
+    client.upload_archive(request).await?;
+
+    Ok(())
+}
+
+pub async fn events(server_address: String) -> Result<Streaming<DockerEvent>> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(Empty {});
+    let stream = client.events(request).await?.into_inner();
+
+    Ok(stream)
+}
+
+pub async fn top(id: String, ps_args: String, server_address: String) -> Result<ProcessList> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(TopRequest { id, ps_args });
+    let processes = client.top(request).await?.into_inner();
+
+    Ok(processes)
+}
+
+pub async fn changes(id: String, server_address: String) -> Result<FilesystemChanges> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(ContainerIdentifier { id });
+    let changes = client.changes(request).await?.into_inner();
+
+    Ok(changes)
+}
+
+pub async fn commit(request: CommitRequest, server_address: String) -> Result<String> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(request);
+    let reply = client.commit(request).await?.into_inner();
+
+    Ok(reply.image_id)
+}
+
+pub async fn export(id: String, server_address: String) -> Result<Streaming<ArchiveChunk>> {
+    let mut client = DockerClient::connect(server_address).await?;
+    let request = tonic::Request::new(ContainerIdentifier { id });
+    let stream = client.export(request).await?.into_inner();
+
+    Ok(stream)
+}
@@ -1,5 +1,6 @@
-use crate::proto::LogReply;
 use crate::proto::{system_client::SystemClient, Empty};
+use crate::proto::{HistoryRequest, LogReply};
+use crate::state::history::Sample;
 use crate::state::info::Info;
 use anyhow::Result;
 use tonic::Streaming;
@@ -11,6 +12,32 @@ pub async fn get_info(server_address: String) -> Result<Info> {
     Ok(Info::from(response.get_ref()))
 }
 
+/// Samples newer than `since`, or the whole history averaged down to `max_points`.
+pub async fn get_history(
+    server_address: String,
+    since: Option<i64>,
+    max_points: u32,
+) -> Result<Vec<Sample>> {
+    let mut client = SystemClient::connect(server_address).await?;
+    let request = match since {
+        Some(since) => HistoryRequest {
+            from: since + 1,
+            ..Default::default()
+        },
+        None => HistoryRequest {
+            max_points,
+            ..Default::default()
+        },
+    };
+    let response = client.get_history(request).await?;
+    Ok(response
+        .get_ref()
+        .samples
+        .iter()
+        .map(Sample::from)
+        .collect())
+}
+
 pub async fn stream_logs(server_address: String) -> Result<Streaming<LogReply>> {
     let mut client = SystemClient::connect(server_address).await?;
     let request = tonic::Request::new(Empty {});
@@ -0,0 +1,20 @@
+use crate::proto::{jobs_client::JobsClient, Empty, JobIdentifier};
+use crate::state::job::Job;
+
+use anyhow::Result;
+
+pub async fn list_jobs(server_address: String) -> Result<Vec<Job>> {
+    let mut client = JobsClient::connect(server_address).await?;
+    let request = tonic::Request::new(Empty {});
+    let response = client.list_jobs(request).await?;
+
+    Ok(response.get_ref().jobs.iter().map(Job::from).collect())
+}
+
+pub async fn run_job(server_address: String, name: String) -> Result<()> {
+    let mut client = JobsClient::connect(server_address).await?;
+    let request = tonic::Request::new(JobIdentifier { name });
+    client.run_job(request).await?;
+
+    Ok(())
+}
@@ -1,3 +1,6 @@
 pub mod compose;
 pub mod docker;
 pub mod info;
+pub mod jobs;
+pub mod schedule;
+pub mod secrets;
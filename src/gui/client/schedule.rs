@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::proto::{
+    schedule_deploy_request, scheduled_deploy::Schedule, scheduler_client::SchedulerClient,
+    DeployRequest, Empty, ScheduleDeployRequest, ScheduledDeployIdentifier,
+};
+use crate::state::schedule::ScheduledDeploy;
+
+pub async fn list_scheduled(server_address: String) -> Result<Vec<ScheduledDeploy>> {
+    let mut client = SchedulerClient::connect(server_address).await?;
+    let request = tonic::Request::new(Empty {});
+    let response = client.list_scheduled(request).await?;
+
+    Ok(response
+        .get_ref()
+        .deploys
+        .iter()
+        .map(ScheduledDeploy::from)
+        .collect())
+}
+
+pub async fn schedule_deploy(
+    server_address: String,
+    path: String,
+    variables: HashMap<String, String>,
+    schedule: Schedule,
+) -> Result<()> {
+    let mut client = SchedulerClient::connect(server_address).await?;
+    let schedule = match schedule {
+        Schedule::At(at) => schedule_deploy_request::Schedule::At(at),
+        Schedule::Cron(expression) => schedule_deploy_request::Schedule::Cron(expression),
+    };
+
+    let request = tonic::Request::new(ScheduleDeployRequest {
+        request: Some(DeployRequest { path, variables }),
+        schedule: Some(schedule),
+    });
+    client.schedule_deploy(request).await?;
+
+    Ok(())
+}
+
+pub async fn cancel_scheduled(server_address: String, id: String) -> Result<()> {
+    let mut client = SchedulerClient::connect(server_address).await?;
+    let request = tonic::Request::new(ScheduledDeployIdentifier { id });
+    client.cancel_scheduled(request).await?;
+
+    Ok(())
+}
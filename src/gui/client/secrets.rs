@@ -0,0 +1,27 @@
+use crate::proto::{secrets_client::SecretsClient, Empty, SecretName, SetSecretRequest};
+
+use anyhow::Result;
+
+pub async fn list_secrets(server_address: String) -> Result<Vec<String>> {
+    let mut client = SecretsClient::connect(server_address).await?;
+    let request = tonic::Request::new(Empty {});
+    let response = client.list_secrets(request).await?;
+
+    Ok(response.into_inner().names)
+}
+
+pub async fn set_secret(server_address: String, name: String, value: String) -> Result<()> {
+    let mut client = SecretsClient::connect(server_address).await?;
+    let request = tonic::Request::new(SetSecretRequest { name, value });
+    client.set_secret(request).await?;
+
+    Ok(())
+}
+
+pub async fn delete_secret(server_address: String, name: String) -> Result<()> {
+    let mut client = SecretsClient::connect(server_address).await?;
+    let request = tonic::Request::new(SecretName { name });
+    client.delete_secret(request).await?;
+
+    Ok(())
+}
@@ -19,6 +19,8 @@ mod state;
 mod ui;
 mod update;
 
+const EVENT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
 mod proto {
     tonic::include_proto!("manager");
 }
@@ -34,6 +36,7 @@ fn main() -> Result<()> {
 
     let app = App::new(config)?;
     app.start_log_stream();
+    app.start_event_stream();
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([320.0, 240.0]),
@@ -96,8 +99,9 @@ impl App {
         if self.last_update.elapsed().as_millis() > self.config.update_interval.into() {
             let tx = self.tx.clone();
             let config = self.config.clone();
+            let history_since = self.state.history.last_timestamp();
             self.rt.spawn(async move {
-                if let Err(err) = update::update(tx, config).await {
+                if let Err(err) = update::update(tx, config, history_since).await {
                     error!("Update error: {err:?}");
                 }
             });
@@ -109,7 +113,7 @@ impl App {
     fn change_state(&mut self) {
         puffin::profile_function!();
 
-        if let Ok(state_change_msg) = self.rx.try_recv() {
+        while let Ok(state_change_msg) = self.rx.try_recv() {
             state_change_msg(&mut self.state);
         }
     }
@@ -125,6 +129,20 @@ impl App {
         });
     }
 
+    fn start_event_stream(&self) {
+        let server_address = self.config.server_address.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            loop {
+                if let Err(err) = update::stream_events(server_address.clone(), tx.clone()).await {
+                    error!("event stream error: {err:?}");
+                }
+                tokio::time::sleep(EVENT_RECONNECT_DELAY).await;
+            }
+        });
+    }
+
     async fn stream_logs(server_address: String, tx: Sender<StateChangeMessage>) -> Result<()> {
         let stream = client::info::stream_logs(server_address).await?;
         Self::handle_log_stream(stream, tx).await
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use crate::proto;
 
@@ -49,3 +49,12 @@ impl From<&proto::ComposeFileDiff> for ComposeFileDiff {
         }
     }
 }
+
+/// Parses `KEY=VALUE` lines, skipping anything without a `=`.
+pub fn parse_variables(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
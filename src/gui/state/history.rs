@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+
+use crate::proto;
+
+/// Samples kept for the charts, an hour at the default update interval.
+pub const MAX_SAMPLES: usize = 3600;
+
+/// One point of the host and container charts, usages are percentages.
+pub struct Sample {
+    /// Unix time in milliseconds.
+    pub timestamp: i64,
+    pub cpu_usage: Vec<f32>,
+    pub memory_usage: f32,
+    pub swap_usage: f32,
+    pub disk_usage: f32,
+    /// Bytes per second.
+    pub network_received: f32,
+    pub network_transmitted: f32,
+    pub containers: Vec<ContainerSample>,
+}
+
+pub struct ContainerSample {
+    pub name: String,
+    pub cpu_usage: f32,
+    pub memory_usage: u64,
+}
+
+impl From<&proto::HistorySample> for Sample {
+    fn from(s: &proto::HistorySample) -> Self {
+        Self {
+            timestamp: s.timestamp,
+            cpu_usage: s.cpu_usage.clone(),
+            memory_usage: percent(s.memory_used, s.memory_total),
+            swap_usage: percent(s.swap_used, s.swap_total),
+            disk_usage: percent(s.disk_used, s.disk_total),
+            network_received: s.network_received as f32,
+            network_transmitted: s.network_transmitted as f32,
+            containers: s
+                .containers
+                .iter()
+                .map(|c| ContainerSample {
+                    name: c.name.trim_start_matches('/').to_owned(),
+                    cpu_usage: c.cpu_usage,
+                    memory_usage: c.memory_usage,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn percent(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f32 / total as f32 * 100.0
+    }
+}
+
+/// Server history, backfilled on connect and extended with every update.
+#[derive(Default)]
+pub struct History {
+    pub samples: VecDeque<Sample>,
+}
+
+impl History {
+    pub fn last_timestamp(&self) -> Option<i64> {
+        self.samples.back().map(|s| s.timestamp)
+    }
+
+    /// Appends samples newer than the last one, overlapping fetches may repeat some.
+    pub fn extend(&mut self, samples: Vec<Sample>) {
+        let last = self.last_timestamp().unwrap_or(i64::MIN);
+        self.samples
+            .extend(samples.into_iter().filter(|s| s.timestamp > last));
+
+        let excess = self.samples.len().saturating_sub(MAX_SAMPLES);
+        self.samples.drain(..excess);
+    }
+}
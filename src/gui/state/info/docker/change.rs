@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use crate::proto;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChangeKind {
+    Modified,
+    Added,
+    Deleted,
+}
+
+impl From<i32> for ChangeKind {
+    fn from(kind: i32) -> Self {
+        match kind {
+            1 => Self::Added,
+            2 => Self::Deleted,
+            _ => Self::Modified,
+        }
+    }
+}
+
+/// A directory level of the container's filesystem changes.
+#[derive(Default)]
+pub struct ChangeTree {
+    pub kind: Option<ChangeKind>,
+    pub children: BTreeMap<String, ChangeTree>,
+}
+
+impl From<proto::FilesystemChanges> for ChangeTree {
+    fn from(c: proto::FilesystemChanges) -> Self {
+        let mut root = Self::default();
+        for change in c.changes {
+            let node = change
+                .path
+                .split('/')
+                .filter(|part| !part.is_empty())
+                .fold(&mut root, |node, part| {
+                    node.children.entry(part.to_owned()).or_default()
+                });
+            node.kind = Some(ChangeKind::from(change.kind));
+        }
+        root
+    }
+}
+
+impl ChangeTree {
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Number of changed paths below this node.
+    pub fn count(&self) -> usize {
+        self.children
+            .values()
+            .map(|child| usize::from(child.kind.is_some()) + child.count())
+            .sum()
+    }
+}
@@ -1,13 +1,18 @@
+use std::time::Instant;
+
 use chrono::DateTime;
 use chrono_humanize::HumanTime;
 
 use crate::proto;
 
+use super::{change::ChangeTree, process::ProcessList};
+
 pub struct Container {
     pub id: String,
     pub name: String,
     pub image: String,
     pub status: String,
+    pub state: ContainerState,
     pub created: String,
     pub ports: Vec<Port>,
     pub logs: Vec<String>,
@@ -21,11 +26,152 @@ impl Container {
             name: c.names.join(", "),
             image: c.image.clone(),
             status: c.status.clone(),
+            state: ContainerState::from(c.state.as_str()),
             created: format!("{} ({:?})", HumanTime::from(created), created),
             ports: c.ports.iter().map(|p| Port::from(p.clone())).collect(),
             logs,
         }
     }
+
+    /// Takes over a newer poll of the same container, keeping the logs when unchanged.
+    pub fn update(&mut self, polled: Container) {
+        self.name = polled.name;
+        self.image = polled.image;
+        self.status = polled.status;
+        self.state = polled.state;
+        self.created = polled.created;
+        self.ports = polled.ports;
+        if self.logs != polled.logs {
+            self.logs = polled.logs;
+        }
+    }
+
+    /// A container only known from its create event.
+    pub fn created(id: &str, name: &str, image: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            name: format!("/{name}"),
+            image: image.to_owned(),
+            status: "Created".to_owned(),
+            state: ContainerState::Created,
+            created: String::new(),
+            ports: Vec::new(),
+            logs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContainerState {
+    Created,
+    Running,
+    Paused,
+    Restarting,
+    Removing,
+    Exited,
+    Dead,
+    Unknown,
+}
+
+impl From<&str> for ContainerState {
+    fn from(state: &str) -> Self {
+        match state {
+            "created" => Self::Created,
+            "running" => Self::Running,
+            "paused" => Self::Paused,
+            "restarting" => Self::Restarting,
+            "removing" => Self::Removing,
+            "exited" => Self::Exited,
+            "dead" => Self::Dead,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl ContainerState {
+    pub fn can_start(self) -> bool {
+        matches!(self, Self::Created | Self::Exited | Self::Dead)
+    }
+
+    pub fn can_stop(self) -> bool {
+        matches!(self, Self::Running | Self::Restarting)
+    }
+
+    pub fn can_restart(self) -> bool {
+        matches!(self, Self::Running | Self::Exited | Self::Created)
+    }
+
+    pub fn can_pause(self) -> bool {
+        self == Self::Running
+    }
+
+    pub fn can_unpause(self) -> bool {
+        self == Self::Paused
+    }
+
+    pub fn can_kill(self) -> bool {
+        matches!(self, Self::Running | Self::Restarting)
+    }
+
+    pub fn can_exec(self) -> bool {
+        self == Self::Running
+    }
+
+    /// Running and paused containers can only be removed with force.
+    pub fn can_remove(self, force: bool) -> bool {
+        match self {
+            Self::Removing => false,
+            Self::Running | Self::Paused | Self::Restarting => force,
+            _ => true,
+        }
+    }
+}
+
+/// Options used by the container actions, shared by all container cards.
+pub struct ContainerActionOptions {
+    pub stop_timeout: String,
+    pub kill_signal: String,
+    pub force_remove: bool,
+    pub remove_volumes: bool,
+}
+
+impl Default for ContainerActionOptions {
+    fn default() -> Self {
+        Self {
+            stop_timeout: String::new(),
+            kill_signal: "SIGKILL".to_owned(),
+            force_remove: false,
+            remove_volumes: false,
+        }
+    }
+}
+
+impl ContainerActionOptions {
+    /// Empty or invalid input uses Docker's default timeout.
+    pub fn stop_timeout(&self) -> Option<u32> {
+        self.stop_timeout.trim().parse().ok()
+    }
+}
+
+/// UI state of a single container card.
+#[derive(Default)]
+pub struct ContainerView {
+    pub rename: String,
+    pub download_path: String,
+    pub download_destination: String,
+    pub upload_source: String,
+    pub upload_destination: String,
+    pub transfer_status: Option<String>,
+    pub ps_args: String,
+    pub processes: Option<Result<ProcessList, String>>,
+    pub processes_requested: Option<Instant>,
+    pub changes: Option<Result<ChangeTree, String>>,
+    pub changes_requested: bool,
+    pub commit_repository: String,
+    pub commit_tag: String,
+    pub export_destination: String,
+    pub snapshot_status: Option<String>,
+    pub action_error: Option<String>,
 }
 
 pub struct Port {
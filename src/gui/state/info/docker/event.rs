@@ -0,0 +1,78 @@
+use chrono::DateTime;
+
+use crate::proto;
+
+use super::container::{Container, ContainerState};
+use super::DockerInfo;
+
+pub const MAX_EVENTS: usize = 100;
+
+pub struct Event {
+    pub time: String,
+    pub event_type: String,
+    pub action: String,
+    pub name: String,
+}
+
+impl From<&proto::DockerEvent> for Event {
+    fn from(e: &proto::DockerEvent) -> Self {
+        let time = DateTime::from_timestamp(e.time, 0).unwrap_or_default();
+        Self {
+            time: time.format("%H:%M:%S").to_string(),
+            event_type: e.event_type.clone(),
+            action: e.action.clone(),
+            name: e
+                .attributes
+                .get("name")
+                .cloned()
+                .unwrap_or_else(|| e.id.chars().take(12).collect()),
+        }
+    }
+}
+
+impl DockerInfo {
+    /// Updates the container list from an event, polls taken before it no longer override it.
+    pub fn apply(&mut self, event: &proto::DockerEvent) {
+        if event.event_type != "container" {
+            return;
+        }
+        self.last_events.insert(event.id.clone(), event.time);
+
+        if event.action == "destroy" {
+            self.containers.retain(|c| c.id != event.id);
+            return;
+        }
+
+        let attribute = |key: &str| event.attributes.get(key).cloned().unwrap_or_default();
+        let index = match self.containers.iter().position(|c| c.id == event.id) {
+            Some(index) => index,
+            None if event.action == "create" => {
+                self.containers.push(Container::created(
+                    &event.id,
+                    &attribute("name"),
+                    &attribute("image"),
+                ));
+                return;
+            }
+            None => return,
+        };
+
+        let container = &mut self.containers[index];
+        match event.action.as_str() {
+            "start" | "unpause" | "restart" => {
+                container.state = ContainerState::Running;
+                container.status = "Up".to_owned();
+            }
+            "pause" => {
+                container.state = ContainerState::Paused;
+                container.status = "Paused".to_owned();
+            }
+            "die" => {
+                container.state = ContainerState::Exited;
+                container.status = format!("Exited ({})", attribute("exitCode"));
+            }
+            "rename" => container.name = format!("/{}", attribute("name")),
+            _ => {}
+        }
+    }
+}
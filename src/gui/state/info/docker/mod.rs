@@ -1,13 +1,93 @@
+use std::collections::HashMap;
+
 use container::Container;
 use version::Version;
 
+pub mod change;
 pub mod container;
+pub mod event;
+pub mod process;
 pub mod version;
 
+/// Container engine behind the Docker API.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Engine {
+    #[default]
+    Unknown,
+    Docker,
+    Podman,
+}
+
+impl Engine {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Unknown => "n/a",
+            Self::Docker => "Docker",
+            Self::Podman => "Podman",
+        }
+    }
+}
+
+impl From<crate::proto::EngineKind> for Engine {
+    fn from(kind: crate::proto::EngineKind) -> Self {
+        match kind {
+            crate::proto::EngineKind::Unknown => Self::Unknown,
+            crate::proto::EngineKind::Docker => Self::Docker,
+            crate::proto::EngineKind::Podman => Self::Podman,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct DockerInfo {
     pub containers: Vec<Container>,
     pub version: Version,
+    pub engine: Engine,
+    /// Time of the last event applied to each container, destroyed ones included.
+    last_events: HashMap<String, i64>,
+}
+
+impl DockerInfo {
+    /// Merges a poll taken at `polled_at` into the current containers. Containers that saw
+    /// an event since keep what the event set, the poll predates it.
+    pub fn merge(&mut self, polled: DockerInfo, polled_at: i64) {
+        self.version = polled.version;
+        self.engine = polled.engine;
+        self.last_events.retain(|_, time| *time >= polled_at);
+
+        let mut previous: Vec<Option<Container>> = self.containers.drain(..).map(Some).collect();
+        let positions: HashMap<String, usize> = previous
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, c)| (c.id.clone(), i))
+            .collect();
+
+        for container in polled.containers {
+            let newer_event = self.last_events.contains_key(&container.id);
+            let existing = positions
+                .get(&container.id)
+                .and_then(|&i| previous[i].take());
+            match existing {
+                Some(existing) if newer_event => self.containers.push(existing),
+                Some(mut existing) => {
+                    existing.update(container);
+                    self.containers.push(existing);
+                }
+                // Destroyed after the poll.
+                None if newer_event => {}
+                None => self.containers.push(container),
+            }
+        }
+
+        // Created after the poll.
+        self.containers.extend(
+            previous
+                .into_iter()
+                .flatten()
+                .filter(|c| self.last_events.contains_key(&c.id)),
+        );
+    }
 }
 
 impl From<crate::proto::DockerInfo> for DockerInfo {
@@ -20,6 +100,8 @@ impl From<crate::proto::DockerInfo> for DockerInfo {
                 .map(|c| Container::new(c, c.logs.clone()))
                 .collect(),
             version: Version::from(&docker_info.version.clone().unwrap_or_default()),
+            engine: docker_info.engine().into(),
+            last_events: HashMap::new(),
         }
     }
 }
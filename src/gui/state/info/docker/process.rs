@@ -0,0 +1,63 @@
+use std::cmp::Ordering;
+
+use crate::proto;
+
+pub struct ProcessList {
+    pub titles: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Sorted column and whether it is ascending.
+    pub sort: Option<(usize, bool)>,
+}
+
+impl From<proto::ProcessList> for ProcessList {
+    fn from(p: proto::ProcessList) -> Self {
+        Self {
+            titles: p.titles,
+            rows: p.processes.into_iter().map(|row| row.values).collect(),
+            sort: None,
+        }
+    }
+}
+
+impl ProcessList {
+    /// Sorts by `column`, flipping the direction when it is already sorted by it.
+    pub fn sort_by(&mut self, column: usize) {
+        let ascending = match self.sort {
+            Some((c, ascending)) if c == column => !ascending,
+            _ => true,
+        };
+        self.sort = Some((column, ascending));
+        self.apply_sort();
+    }
+
+    /// Keeps the previous sort order when the list is refreshed.
+    pub fn sorted_like(mut self, previous: Option<&ProcessList>) -> Self {
+        self.sort = previous.and_then(|p| p.sort);
+        self.apply_sort();
+        self
+    }
+
+    fn apply_sort(&mut self) {
+        let Some((column, ascending)) = self.sort else {
+            return;
+        };
+
+        self.rows.sort_by(|a, b| {
+            let ordering = compare(a.get(column), b.get(column));
+            if ascending {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+        });
+    }
+}
+
+/// Numbers compare numerically so PID, %CPU and RSS sort as expected.
+fn compare(a: Option<&String>, b: Option<&String>) -> Ordering {
+    let (a, b) = (a.map_or("", String::as_str), b.map_or("", String::as_str));
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
@@ -3,6 +3,7 @@ use crate::proto;
 pub struct Version {
     pub version: String,
     pub api_version: String,
+    pub negotiated_api_version: String,
 }
 
 impl From<&proto::Version> for Version {
@@ -10,6 +11,7 @@ impl From<&proto::Version> for Version {
         Version {
             version: v.version.clone(),
             api_version: v.api_version.clone(),
+            negotiated_api_version: v.negotiated_api_version.clone(),
         }
     }
 }
@@ -19,6 +21,7 @@ impl Default for Version {
         Self {
             version: "n/a".to_string(),
             api_version: "n/a".to_string(),
+            negotiated_api_version: "n/a".to_string(),
         }
     }
 }
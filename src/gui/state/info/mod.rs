@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use cpu::Cpu;
 use disk::Disk;
 use docker::DockerInfo;
@@ -21,6 +22,21 @@ pub struct Info {
     pub disks: Vec<Disk>,
     pub cpus: Vec<Cpu>,
     pub docker_info: DockerInfo,
+    /// Why the server could not reach Docker, the Docker info then is stale.
+    pub docker_error: Option<String>,
+    pub docker_updated_at: Option<DateTime<Utc>>,
+}
+
+impl Info {
+    /// Takes over a newer reply, merging its containers into the current ones.
+    pub fn update(&mut self, info: Info) {
+        let polled_at = info.docker_updated_at.map_or(0, |t| t.timestamp());
+        self.docker_info.merge(info.docker_info, polled_at);
+        *self = Info {
+            docker_info: std::mem::take(&mut self.docker_info),
+            ..info
+        };
+    }
 }
 
 impl From<&InfoReply> for Info {
@@ -35,6 +51,10 @@ impl From<&InfoReply> for Info {
             disks: disks(i.clone()),
             cpus: cpus(i.clone()),
             docker_info: i.docker_info.clone().unwrap_or_default().into(),
+            docker_error: Some(i.docker_error.clone()).filter(|e| !e.is_empty()),
+            docker_updated_at: Some(i.docker_updated_at)
+                .filter(|t| *t > 0)
+                .and_then(|t| DateTime::from_timestamp(t, 0)),
         }
     }
 }
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Local};
+
+use crate::proto;
+
+pub struct JobRun {
+    pub started: String,
+    pub duration: String,
+    pub exit_code: i64,
+    pub logs: Vec<String>,
+}
+
+impl From<&proto::JobRun> for JobRun {
+    fn from(r: &proto::JobRun) -> Self {
+        Self {
+            started: format_timestamp(r.started),
+            duration: format!("{}s", r.finished - r.started),
+            exit_code: r.exit_code,
+            logs: r.logs.clone(),
+        }
+    }
+}
+
+pub struct Job {
+    pub name: String,
+    pub image: String,
+    pub command: String,
+    pub schedule: String,
+    pub next_run: String,
+    pub running: bool,
+    pub runs: Vec<JobRun>,
+    pub definition_error: Option<String>,
+}
+
+impl From<&proto::Job> for Job {
+    fn from(j: &proto::Job) -> Self {
+        Self {
+            name: j.name.clone(),
+            image: j.image.clone(),
+            command: j.command.join(" "),
+            schedule: j.schedule.clone(),
+            next_run: j
+                .next_run
+                .map(format_timestamp)
+                .unwrap_or_else(|| "n/a".to_string()),
+            running: j.running,
+            runs: j.runs.iter().map(JobRun::from).collect(),
+            definition_error: Some(j.definition_error.clone()).filter(|e| !e.is_empty()),
+        }
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| {
+            t.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "n/a".to_string())
+}
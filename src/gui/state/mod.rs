@@ -1,14 +1,51 @@
+use std::collections::{HashMap, VecDeque};
+
 use compose::ComposeFileDiff;
+use history::History;
+use info::docker::{
+    container::{ContainerActionOptions, ContainerView},
+    event::Event,
+};
 use info::Info;
+use job::Job;
 use log::ServerLog;
+use schedule::ScheduledDeploy;
+use terminal::Terminal;
 
 pub mod compose;
+pub mod history;
 pub mod info;
+pub mod job;
 pub mod log;
+pub mod schedule;
+pub mod terminal;
+
+#[derive(Default, PartialEq)]
+pub enum Tab {
+    #[default]
+    Overview,
+    Scheduled,
+    Jobs,
+}
 
 #[derive(Default)]
 pub struct State {
+    pub tab: Tab,
     pub info: Info,
+    pub history: History,
     pub server_log: ServerLog,
     pub compose_file_diffs: Vec<ComposeFileDiff>,
+    pub deploy_output: Vec<String>,
+    pub deploy_variables: String,
+    pub secrets: Vec<String>,
+    pub secret_name: String,
+    pub secret_value: String,
+    pub scheduled_deploys: Vec<ScheduledDeploy>,
+    pub schedule_path: String,
+    pub schedule_when: String,
+    pub jobs: Vec<Job>,
+    pub terminal: Option<Terminal>,
+    pub container_options: ContainerActionOptions,
+    pub container_views: HashMap<String, ContainerView>,
+    pub docker_events: VecDeque<Event>,
 }
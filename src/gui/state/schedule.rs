@@ -0,0 +1,64 @@
+use chrono::{DateTime, NaiveDateTime};
+
+use crate::proto::{self, scheduled_deploy::Schedule};
+
+pub struct Outcome {
+    pub time: String,
+    pub success: bool,
+    pub message: String,
+}
+
+impl From<&proto::DeployOutcome> for Outcome {
+    fn from(o: &proto::DeployOutcome) -> Self {
+        Self {
+            time: format_timestamp(o.time),
+            success: o.success,
+            message: o.message.clone(),
+        }
+    }
+}
+
+pub struct ScheduledDeploy {
+    pub id: String,
+    pub path: String,
+    pub schedule: String,
+    pub next_run: String,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl From<&proto::ScheduledDeploy> for ScheduledDeploy {
+    fn from(s: &proto::ScheduledDeploy) -> Self {
+        let schedule = match &s.schedule {
+            Some(Schedule::At(at)) => format!("once at {}", format_timestamp(*at)),
+            Some(Schedule::Cron(expression)) => format!("cron '{expression}'"),
+            None => "n/a".to_string(),
+        };
+
+        Self {
+            id: s.id.clone(),
+            path: s.request.clone().unwrap_or_default().path,
+            schedule,
+            next_run: s
+                .next_run
+                .map(format_timestamp)
+                .unwrap_or_else(|| "done".to_string()),
+            outcomes: s.outcomes.iter().map(Outcome::from).collect(),
+        }
+    }
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| "n/a".to_string())
+}
+
+/// `YYYY-MM-DD HH:MM` schedules a single deploy, anything else is treated as a cron
+/// expression. Both are in UTC like the server evaluates cron expressions.
+pub fn parse_schedule(when: &str) -> Schedule {
+    let when = when.trim();
+    NaiveDateTime::parse_from_str(when, "%Y-%m-%d %H:%M")
+        .ok()
+        .map(|t| Schedule::At(t.and_utc().timestamp()))
+        .unwrap_or_else(|| Schedule::Cron(when.to_string()))
+}
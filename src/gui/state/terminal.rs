@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+
+use tokio::sync::mpsc::Sender;
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+use crate::proto::ExecInput;
+
+const MAX_SCROLLBACK: usize = 5000;
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TerminalColor {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<TerminalColor>,
+    pub bold: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+#[derive(Debug, Default)]
+pub struct Line {
+    pub spans: Vec<Span>,
+}
+
+impl Line {
+    fn push(&mut self, text: &str, style: Style) {
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.text.push_str(text),
+            _ => self.spans.push(Span {
+                text: text.to_owned(),
+                style,
+            }),
+        }
+    }
+
+    fn backspace(&mut self) {
+        if let Some(span) = self.spans.last_mut() {
+            span.text.pop();
+            if span.text.is_empty() {
+                self.spans.pop();
+            }
+        }
+    }
+}
+
+pub struct Terminal {
+    /// Tags the output of this terminal's exec, so a replaced session cannot write into it.
+    pub session: Uuid,
+    /// The exec task, aborted when the terminal is closed or replaced.
+    pub task: Option<AbortHandle>,
+    pub container_id: String,
+    pub container_name: String,
+    pub lines: VecDeque<Line>,
+    pub input: String,
+    pub size: (u32, u32),
+    pub exit_code: Option<i64>,
+    pub error: Option<String>,
+    pub sender: Sender<ExecInput>,
+    style: Style,
+    carriage_return: bool,
+    pending: Vec<u8>,
+}
+
+impl Terminal {
+    pub fn new(container_id: String, container_name: String, sender: Sender<ExecInput>) -> Self {
+        Self {
+            session: Uuid::new_v4(),
+            task: None,
+            container_id,
+            container_name,
+            lines: VecDeque::from([Line::default()]),
+            input: String::new(),
+            size: (0, 0),
+            exit_code: None,
+            error: None,
+            sender,
+            style: Style::default(),
+            carriage_return: false,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.exit_code.is_none() && self.error.is_none()
+    }
+
+    /// Appends raw TTY output, keeping incomplete escape sequences and
+    /// characters around until the rest arrives.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(bytes);
+
+        let mut i = 0;
+        while i < data.len() {
+            match data[i] {
+                ESC => match escape_length(&data[i..]) {
+                    Some(length) => {
+                        let sequence = &data[i..i + length];
+                        if sequence.len() > 2 && sequence[1] == b'[' && sequence[length - 1] == b'm'
+                        {
+                            self.apply_sgr(&sequence[2..length - 1]);
+                        }
+                        i += length;
+                    }
+                    None => {
+                        self.pending = data[i..].to_vec();
+                        return;
+                    }
+                },
+                b'\n' => {
+                    self.carriage_return = false;
+                    self.new_line();
+                    i += 1;
+                }
+                b'\r' => {
+                    self.carriage_return = true;
+                    i += 1;
+                }
+                0x08 => {
+                    self.current_line().backspace();
+                    i += 1;
+                }
+                b'\t' => {
+                    self.write("\t");
+                    i += 1;
+                }
+                b if b < 0x20 || b == 0x7f => i += 1,
+                _ => {
+                    let end = data[i..]
+                        .iter()
+                        .position(|&b| b < 0x20 || b == 0x7f)
+                        .map_or(data.len(), |p| i + p);
+
+                    let text = &data[i..end];
+                    match std::str::from_utf8(text) {
+                        Ok(text) => self.write(text),
+                        Err(err) if err.error_len().is_none() && end == data.len() => {
+                            let valid = err.valid_up_to();
+                            self.write(&String::from_utf8_lossy(&text[..valid]));
+                            self.pending = text[valid..].to_vec();
+                            return;
+                        }
+                        Err(_) => self.write(&String::from_utf8_lossy(text)),
+                    }
+                    i = end;
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if std::mem::take(&mut self.carriage_return) {
+            *self.current_line() = Line::default();
+        }
+        let style = self.style;
+        self.current_line().push(text, style);
+    }
+
+    fn new_line(&mut self) {
+        self.lines.push_back(Line::default());
+        while self.lines.len() > MAX_SCROLLBACK {
+            self.lines.pop_front();
+        }
+    }
+
+    fn current_line(&mut self) -> &mut Line {
+        if self.lines.is_empty() {
+            self.lines.push_back(Line::default());
+        }
+        let last = self.lines.len() - 1;
+        &mut self.lines[last]
+    }
+
+    fn apply_sgr(&mut self, params: &[u8]) {
+        let params = String::from_utf8_lossy(params);
+        let mut codes = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+
+        while let Some(code) = codes.next() {
+            match code {
+                0 => self.style = Style::default(),
+                1 => self.style.bold = true,
+                22 => self.style.bold = false,
+                30..=37 => self.style.color = Some(TerminalColor::Indexed(code - 30)),
+                90..=97 => self.style.color = Some(TerminalColor::Indexed(code - 90 + 8)),
+                39 => self.style.color = None,
+                38 => self.style.color = extended_color(&mut codes),
+                48 => {
+                    extended_color(&mut codes);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+/// Reads the `5;n` or `2;r;g;b` tail of an extended color code.
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<TerminalColor> {
+    match codes.next() {
+        Some(5) => codes.next().map(TerminalColor::Indexed),
+        Some(2) => {
+            let r = codes.next()?;
+            let g = codes.next()?;
+            let b = codes.next()?;
+            Some(TerminalColor::Rgb(r, g, b))
+        }
+        _ => None,
+    }
+}
+
+/// Length of the escape sequence at the start of `data`, `None` if it is incomplete.
+fn escape_length(data: &[u8]) -> Option<usize> {
+    match data.get(1)? {
+        b'[' => data[2..]
+            .iter()
+            .position(|b| (0x40..=0x7e).contains(b))
+            .map(|p| p + 3),
+        b']' => data[2..].iter().enumerate().find_map(|(p, &b)| match b {
+            BEL => Some(p + 3),
+            ESC if data.get(p + 3) == Some(&b'\\') => Some(p + 4),
+            _ => None,
+        }),
+        _ => Some(2),
+    }
+}
@@ -1,13 +1,13 @@
 use std::path::PathBuf;
 
-use egui::{Color32, RichText, Ui};
+use egui::{CollapsingHeader, Color32, RichText, ScrollArea, TextStyle, Ui};
 use tracing::error;
 
-use crate::state::compose::{ComposeFileDiff, DiffResult};
+use crate::state::compose::{parse_variables, ComposeFileDiff, DiffResult};
 use crate::{client, update, App};
 
 impl App {
-    pub fn compose(&self, ui: &mut Ui) {
+    pub fn compose(&mut self, ui: &mut Ui) {
         puffin::profile_function!();
 
         ui.horizontal(|ui| {
@@ -23,17 +23,26 @@ impl App {
             }
         });
 
-        if self.state.compose_file_diffs.is_empty() {
-            return;
-        }
+        CollapsingHeader::new(RichText::new("Variables").color(Color32::WHITE))
+            .id_source("deploy_variables")
+            .show(ui, |ui| {
+                ui.label("KEY=VALUE per line, overrides the server's .env");
+                ui.text_edit_multiline(&mut self.state.deploy_variables);
+            });
 
-        ui.group(|ui| {
-            ui.vertical(|ui| {
-                for f in &self.state.compose_file_diffs {
-                    self.file(ui, f);
-                }
+        if !self.state.compose_file_diffs.is_empty() {
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    for f in &self.state.compose_file_diffs {
+                        self.file(ui, f);
+                    }
+                });
             });
-        });
+        }
+
+        if !self.state.deploy_output.is_empty() {
+            deploy_output(ui, &self.state.deploy_output);
+        }
     }
 
     pub fn file(&self, ui: &mut Ui, diff: &ComposeFileDiff) {
@@ -52,6 +61,9 @@ impl App {
                 if ui.button("Deploy").clicked() {
                     self.deploy(diff.path.clone());
                 }
+                if ui.button("Undeploy").clicked() {
+                    self.undeploy(diff.path.clone());
+                }
                 return;
             }
 
@@ -79,11 +91,49 @@ impl App {
 
     fn deploy(&self, path: PathBuf) {
         let config = self.config.clone();
+        let tx = self.tx.clone();
+        let variables = parse_variables(&self.state.deploy_variables);
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::deploy(config, path, variables, tx).await {
+                error!("{err:?}");
+            }
+        });
+    }
+
+    fn undeploy(&self, path: PathBuf) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+        let variables = parse_variables(&self.state.deploy_variables);
 
         self.rt.spawn(async move {
-            if let Err(err) = client::compose::deploy(config.server_address.clone(), path).await {
+            if let Err(err) = update::undeploy(config, path, variables, tx).await {
                 error!("{err:?}");
             }
         });
     }
 }
+
+fn deploy_output(ui: &mut Ui, output: &[String]) {
+    puffin::profile_function!();
+
+    CollapsingHeader::new(RichText::new("Deploy output").color(Color32::WHITE))
+        .default_open(true)
+        .show(ui, |ui| {
+            ScrollArea::vertical()
+                .id_source("deploy_output")
+                .max_height(400.0)
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show_rows(
+                    ui,
+                    ui.text_style_height(&TextStyle::Monospace),
+                    output.len(),
+                    |ui, row_range| {
+                        for line in &output[row_range.start..row_range.end] {
+                            ui.label(RichText::new(line).monospace());
+                        }
+                    },
+                );
+        });
+}
@@ -1,25 +1,63 @@
-use egui::{CollapsingHeader, Color32, RichText, ScrollArea, TextStyle, Ui};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use chrono_humanize::HumanTime;
+use egui::{
+    Button, CollapsingHeader, Color32, Grid, RichText, ScrollArea, TextEdit, TextStyle, Ui,
+};
 use tracing::error;
 
 use crate::state::info::docker::{
-    container::{Container, Port},
+    change::{ChangeKind, ChangeTree},
+    container::{Container, ContainerActionOptions, ContainerView, Port},
+    event::Event,
+    process::ProcessList,
     version::Version,
+    Engine,
 };
-use crate::{client, App};
+use crate::{client, update, App};
+
+const TOP_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
 
 impl App {
-    pub fn docker(&self, ui: &mut Ui) {
+    pub fn docker(&mut self, ui: &mut Ui) {
         puffin::profile_function!();
 
         ui.heading(RichText::new("Docker").color(Color32::WHITE));
-        version(ui, &self.state.info.docker_info.version);
+        if let Some(error) = &self.state.info.docker_error {
+            unreachable_banner(ui, error, self.state.info.docker_updated_at);
+        }
+        version(
+            ui,
+            &self.state.info.docker_info.version,
+            self.state.info.docker_info.engine,
+        );
+        action_options(ui, &mut self.state.container_options);
+        events(ui, &self.state.docker_events);
 
+        let mut views = std::mem::take(&mut self.state.container_views);
+        views.retain(|id, _| {
+            self.state
+                .info
+                .docker_info
+                .containers
+                .iter()
+                .any(|c| &c.id == id)
+        });
         for c in &self.state.info.docker_info.containers {
-            self.container(ui, c);
+            let view = views.entry(c.id.clone()).or_default();
+            self.container(ui, c, view);
         }
+        self.state.container_views = views;
     }
 
-    fn container(&self, ui: &mut Ui, container: &Container) {
+    fn container(&self, ui: &mut Ui, container: &Container, view: &mut ContainerView) {
         puffin::profile_function!();
 
         ui.group(|ui| {
@@ -53,67 +91,519 @@ impl App {
             });
 
             logs(ui, container);
-            self.docker_actions(ui, &container.id);
+            self.processes(ui, container, view);
+            self.changes(ui, container, view);
+            self.files(ui, container, view);
+            self.snapshot(ui, container, view);
+            self.docker_actions(ui, container, view);
         });
     }
 
-    fn docker_actions(&self, ui: &mut Ui, id: &str) {
+    fn docker_actions(&self, ui: &mut Ui, container: &Container, view: &mut ContainerView) {
         puffin::profile_function!();
 
+        let id = &container.id;
+        let state = container.state;
+        let options = &self.state.container_options;
+        let server_address = self.config.server_address.clone();
+
         ui.horizontal(|ui| {
-            if ui.button("Start").clicked() {
-                self.start_container(id.to_owned())
+            if ui
+                .add_enabled(state.can_start(), Button::new("Start"))
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::start_container(id.to_owned(), server_address.clone()),
+                );
             }
-            if ui.button("Stop").clicked() {
-                self.stop_container(id.to_owned())
+            if ui
+                .add_enabled(state.can_stop(), Button::new("Stop"))
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::stop_container(
+                        id.to_owned(),
+                        options.stop_timeout(),
+                        server_address.clone(),
+                    ),
+                );
             }
-            if ui.button("Remove").clicked() {
-                self.remove_container(id.to_owned())
+            if ui
+                .add_enabled(state.can_restart(), Button::new("Restart"))
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::restart_container(
+                        id.to_owned(),
+                        options.stop_timeout(),
+                        server_address.clone(),
+                    ),
+                );
+            }
+            if ui
+                .add_enabled(state.can_pause(), Button::new("Pause"))
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::pause_container(id.to_owned(), server_address.clone()),
+                );
+            }
+            if ui
+                .add_enabled(state.can_unpause(), Button::new("Unpause"))
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::unpause_container(id.to_owned(), server_address.clone()),
+                );
+            }
+            if ui
+                .add_enabled(state.can_kill(), Button::new("Kill"))
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::kill_container(
+                        id.to_owned(),
+                        options.kill_signal.trim().to_owned(),
+                        server_address.clone(),
+                    ),
+                );
+            }
+            if ui
+                .add_enabled(
+                    state.can_remove(options.force_remove),
+                    Button::new("Remove"),
+                )
+                .clicked()
+            {
+                self.container_action(
+                    id,
+                    client::docker::remove_container(
+                        id.to_owned(),
+                        options.force_remove,
+                        options.remove_volumes,
+                        server_address.clone(),
+                    ),
+                );
+            }
+            if ui
+                .add_enabled(state.can_exec(), Button::new("Terminal"))
+                .clicked()
+            {
+                self.open_terminal(id.to_owned(), container.name.clone())
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut view.rename).hint_text("New name"));
+            if ui
+                .add_enabled(!view.rename.trim().is_empty(), Button::new("Rename"))
+                .clicked()
+            {
+                let name = std::mem::take(&mut view.rename).trim().to_owned();
+                self.container_action(
+                    id,
+                    client::docker::rename_container(id.to_owned(), name, server_address),
+                );
             }
         });
+
+        if let Some(error) = &view.action_error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
     }
 
-    fn start_container(&self, id: String) {
+    fn files(&self, ui: &mut Ui, container: &Container, view: &mut ContainerView) {
         puffin::profile_function!();
 
-        let server_address = self.config.clone().server_address;
+        CollapsingHeader::new(RichText::new("Files").color(Color32::WHITE))
+            .id_source(format!("{}-files", &container.id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Download").color(Color32::WHITE));
+                    ui.add(
+                        TextEdit::singleline(&mut view.download_path)
+                            .hint_text("Path in container"),
+                    );
+                    ui.add(
+                        TextEdit::singleline(&mut view.download_destination)
+                            .hint_text("Local directory"),
+                    );
+                    let ready = !view.download_path.trim().is_empty()
+                        && !view.download_destination.trim().is_empty();
+                    if ui.add_enabled(ready, Button::new("Download")).clicked() {
+                        view.transfer_status = Some("Downloading...".to_owned());
+                        self.download(
+                            container.id.clone(),
+                            view.download_path.trim().to_owned(),
+                            view.download_destination.trim().into(),
+                        );
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Upload").color(Color32::WHITE));
+                    ui.add(
+                        TextEdit::singleline(&mut view.upload_source)
+                            .hint_text("Local file or directory"),
+                    );
+                    ui.add(
+                        TextEdit::singleline(&mut view.upload_destination)
+                            .hint_text("Directory in container"),
+                    );
+                    let ready = !view.upload_source.trim().is_empty()
+                        && !view.upload_destination.trim().is_empty();
+                    if ui.add_enabled(ready, Button::new("Upload")).clicked() {
+                        view.transfer_status = Some("Uploading...".to_owned());
+                        self.upload(
+                            container.id.clone(),
+                            view.upload_source.trim().into(),
+                            view.upload_destination.trim().to_owned(),
+                        );
+                    }
+                });
+                if let Some(status) = &view.transfer_status {
+                    ui.label(status);
+                }
+            });
+    }
+
+    fn processes(&self, ui: &mut Ui, container: &Container, view: &mut ContainerView) {
+        puffin::profile_function!();
+
+        CollapsingHeader::new(RichText::new("Processes").color(Color32::WHITE))
+            .id_source(format!("{}-processes", &container.id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("ps args").color(Color32::WHITE));
+                    ui.add(
+                        TextEdit::singleline(&mut view.ps_args)
+                            .hint_text("-ef")
+                            .desired_width(120.0),
+                    );
+                });
+
+                let due = view
+                    .processes_requested
+                    .is_none_or(|requested| requested.elapsed() > TOP_REFRESH_INTERVAL);
+                if due && container.state.can_exec() {
+                    view.processes_requested = Some(Instant::now());
+                    self.top(container.id.clone(), view.ps_args.trim().to_owned());
+                }
+
+                match &mut view.processes {
+                    Some(Ok(processes)) => process_table(ui, &container.id, processes),
+                    Some(Err(err)) => {
+                        ui.label(RichText::new(err.as_str()).color(Color32::RED));
+                    }
+                    None => {
+                        ui.label("Loading...");
+                    }
+                }
+            });
+    }
+
+    fn top(&self, id: String, ps_args: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
 
         self.rt.spawn(async move {
-            if let Err(err) = client::docker::start_container(id, server_address).await {
+            if let Err(err) = update::update_processes(config, id, ps_args, tx).await {
                 error!("{err:?}");
             }
         });
     }
 
-    fn stop_container(&self, id: String) {
+    fn changes(&self, ui: &mut Ui, container: &Container, view: &mut ContainerView) {
         puffin::profile_function!();
 
-        let server_address = self.config.clone().server_address;
+        let header = CollapsingHeader::new(RichText::new("Changes").color(Color32::WHITE))
+            .id_source(format!("{}-changes", &container.id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("⟳").clicked() {
+                        self.refresh_changes(container.id.clone());
+                    }
+                    if let Some(Ok(tree)) = &view.changes {
+                        ui.label(format!("{} changed paths", tree.count()));
+                    }
+                });
+
+                match &view.changes {
+                    Some(Ok(tree)) if tree.is_empty() => {
+                        ui.label("No changes");
+                    }
+                    Some(Ok(tree)) => {
+                        ScrollArea::vertical()
+                            .id_source(format!("{}-changes-scroll", &container.id))
+                            .max_height(400.0)
+                            .show(ui, |ui| {
+                                change_tree(ui, &container.id, "", tree);
+                            });
+                    }
+                    Some(Err(err)) => {
+                        ui.label(RichText::new(err.as_str()).color(Color32::RED));
+                    }
+                    None => {
+                        ui.label("Loading...");
+                    }
+                }
+            });
+
+        if header.fully_open() && !view.changes_requested {
+            view.changes_requested = true;
+            self.refresh_changes(container.id.clone());
+        }
+    }
+
+    fn refresh_changes(&self, id: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
 
         self.rt.spawn(async move {
-            if let Err(err) = client::docker::stop_container(id, server_address).await {
+            if let Err(err) = update::update_changes(config, id, tx).await {
                 error!("{err:?}");
             }
         });
     }
 
-    fn remove_container(&self, id: String) {
+    fn snapshot(&self, ui: &mut Ui, container: &Container, view: &mut ContainerView) {
         puffin::profile_function!();
 
-        let server_address = self.config.clone().server_address;
+        CollapsingHeader::new(RichText::new("Snapshot").color(Color32::WHITE))
+            .id_source(format!("{}-snapshot", &container.id))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Commit").color(Color32::WHITE));
+                    ui.add(
+                        TextEdit::singleline(&mut view.commit_repository).hint_text("Repository"),
+                    );
+                    ui.add(
+                        TextEdit::singleline(&mut view.commit_tag)
+                            .hint_text("latest")
+                            .desired_width(100.0),
+                    );
+                    let ready = !view.commit_repository.trim().is_empty();
+                    if ui.add_enabled(ready, Button::new("Commit")).clicked() {
+                        view.snapshot_status = Some("Committing...".to_owned());
+                        let tag = match view.commit_tag.trim() {
+                            "" => "latest",
+                            tag => tag,
+                        };
+                        self.commit(
+                            container.id.clone(),
+                            view.commit_repository.trim().to_owned(),
+                            tag.to_owned(),
+                        );
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Export").color(Color32::WHITE));
+                    ui.add(
+                        TextEdit::singleline(&mut view.export_destination)
+                            .hint_text("Local .tar file"),
+                    );
+                    let ready = !view.export_destination.trim().is_empty();
+                    if ui.add_enabled(ready, Button::new("Export")).clicked() {
+                        view.snapshot_status = Some("Exporting...".to_owned());
+                        self.export(container.id.clone(), view.export_destination.trim().into());
+                    }
+                });
+                if let Some(status) = &view.snapshot_status {
+                    ui.label(status);
+                }
+            });
+    }
+
+    fn commit(&self, id: String, repository: String, tag: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
 
         self.rt.spawn(async move {
-            if let Err(err) = client::docker::remove_container(id, server_address).await {
+            if let Err(err) = update::commit_container(config, id, repository, tag, tx).await {
                 error!("{err:?}");
             }
         });
     }
+
+    fn export(&self, id: String, destination: PathBuf) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::export_container(config, id, destination, tx).await {
+                error!("{err:?}");
+            }
+        });
+    }
+
+    fn download(&self, id: String, path: String, destination: PathBuf) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::download_archive(config, id, path, destination, tx).await {
+                error!("{err:?}");
+            }
+        });
+    }
+
+    fn upload(&self, id: String, source: PathBuf, destination: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::upload_file(config, id, source, destination, tx).await {
+                error!("{err:?}");
+            }
+        });
+    }
+
+    fn container_action(
+        &self,
+        id: &str,
+        action: impl Future<Output = Result<()>> + Send + 'static,
+    ) {
+        puffin::profile_function!();
+
+        let id = id.to_owned();
+        let tx = self.tx.clone();
+        self.rt.spawn(async move {
+            if let Err(err) = update::container_action(id, action, tx).await {
+                error!("{err:?}");
+            }
+        });
+    }
+}
+
+fn unreachable_banner(ui: &mut Ui, error: &str, updated_at: Option<DateTime<Utc>>) {
+    let last_update = match updated_at {
+        Some(updated_at) => format!("showing data from {}", HumanTime::from(updated_at)),
+        None => "no data received yet".to_owned(),
+    };
+
+    ui.group(|ui| {
+        ui.label(
+            RichText::new(format!("Docker unreachable, {last_update}"))
+                .color(Color32::RED)
+                .strong(),
+        );
+        ui.label(RichText::new(error).color(Color32::LIGHT_RED));
+    });
+}
+
+fn action_options(ui: &mut Ui, options: &mut ContainerActionOptions) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Stop timeout").color(Color32::WHITE));
+        ui.add(
+            TextEdit::singleline(&mut options.stop_timeout)
+                .hint_text("default")
+                .desired_width(60.0),
+        );
+        ui.label(RichText::new("Kill signal").color(Color32::WHITE));
+        ui.add(TextEdit::singleline(&mut options.kill_signal).desired_width(80.0));
+        ui.checkbox(&mut options.force_remove, "Force remove");
+        ui.checkbox(&mut options.remove_volumes, "Remove volumes");
+    });
+}
+
+fn change_tree(ui: &mut Ui, id: &str, path: &str, tree: &ChangeTree) {
+    for (name, child) in &tree.children {
+        let child_path = format!("{path}/{name}");
+        let text = match child.kind {
+            Some(ChangeKind::Added) => RichText::new(format!("A {name}")).color(Color32::GREEN),
+            Some(ChangeKind::Modified) => RichText::new(format!("M {name}")).color(Color32::YELLOW),
+            Some(ChangeKind::Deleted) => RichText::new(format!("D {name}")).color(Color32::RED),
+            None => RichText::new(format!("  {name}")),
+        }
+        .monospace();
+
+        if child.is_empty() {
+            ui.label(text);
+        } else {
+            CollapsingHeader::new(text)
+                .id_source(format!("{id}-change-{child_path}"))
+                .show(ui, |ui| change_tree(ui, id, &child_path, child));
+        }
+    }
+}
+
+fn process_table(ui: &mut Ui, id: &str, processes: &mut ProcessList) {
+    puffin::profile_function!();
+
+    ScrollArea::horizontal()
+        .id_source(format!("{id}-process-table"))
+        .show(ui, |ui| {
+            Grid::new(format!("{id}-processes"))
+                .striped(true)
+                .show(ui, |ui| {
+                    let mut clicked = None;
+                    for (column, title) in processes.titles.iter().enumerate() {
+                        let marker = match processes.sort {
+                            Some((c, true)) if c == column => " ⏶",
+                            Some((c, false)) if c == column => " ⏷",
+                            _ => "",
+                        };
+                        if ui
+                            .button(RichText::new(format!("{title}{marker}")).color(Color32::WHITE))
+                            .clicked()
+                        {
+                            clicked = Some(column);
+                        }
+                    }
+                    ui.end_row();
+
+                    if let Some(column) = clicked {
+                        processes.sort_by(column);
+                    }
+
+                    for row in &processes.rows {
+                        for value in row {
+                            ui.label(RichText::new(value).monospace());
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+fn events(ui: &mut Ui, events: &VecDeque<Event>) {
+    puffin::profile_function!();
+
+    CollapsingHeader::new(RichText::new("Events").color(Color32::WHITE))
+        .id_source("docker_events")
+        .show(ui, |ui| {
+            ScrollArea::vertical()
+                .id_source("docker_events_scroll")
+                .max_height(200.0)
+                .auto_shrink([false, true])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for e in events {
+                        ui.label(
+                            RichText::new(format!(
+                                "{} {} {} {}",
+                                e.time, e.event_type, e.action, e.name
+                            ))
+                            .monospace(),
+                        );
+                    }
+                });
+        });
 }
 
-fn version(ui: &mut Ui, version: &Version) {
+fn version(ui: &mut Ui, version: &Version, engine: Engine) {
     puffin::profile_function!();
 
     ui.horizontal(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Engine").color(Color32::WHITE));
+            ui.label(engine.name());
+        });
         ui.horizontal(|ui| {
             ui.label(RichText::new("Version").color(Color32::WHITE));
             ui.label(&version.version);
@@ -122,6 +612,10 @@ fn version(ui: &mut Ui, version: &Version) {
             ui.label(RichText::new("Api version").color(Color32::WHITE));
             ui.label(&version.api_version);
         });
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Negotiated api").color(Color32::WHITE));
+            ui.label(&version.negotiated_api_version);
+        });
     });
 }
 
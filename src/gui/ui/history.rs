@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local};
+use egui::{pos2, vec2, Color32, RichText, Sense, Shape, Stroke, Ui};
+use humansize::DECIMAL;
+
+use crate::state::history::{ContainerSample, History, Sample};
+
+const CHART_SIZE: egui::Vec2 = vec2(320.0, 100.0);
+const COLORS: [Color32; 8] = [
+    Color32::LIGHT_BLUE,
+    Color32::LIGHT_GREEN,
+    Color32::GOLD,
+    Color32::LIGHT_RED,
+    Color32::KHAKI,
+    Color32::LIGHT_YELLOW,
+    Color32::LIGHT_GRAY,
+    Color32::BROWN,
+];
+
+/// A line of a chart, as unix milliseconds and value pairs.
+struct Series {
+    name: String,
+    points: Vec<(i64, f32)>,
+}
+
+pub fn history(ui: &mut Ui, history: &History) {
+    puffin::profile_function!();
+
+    ui.vertical(|ui| {
+        ui.heading(RichText::new("History").color(Color32::WHITE));
+        if history.samples.is_empty() {
+            ui.label("No samples yet");
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            chart(ui, "CPU", cpu(history), Some(100.0), percent);
+            chart(ui, "Memory and disk", usage(history), Some(100.0), percent);
+            chart(ui, "Network", network(history), None, throughput);
+            let cpu = containers(history, |c| c.cpu_usage);
+            chart(ui, "Containers CPU", cpu, None, percent);
+            let memory = containers(history, |c| c.memory_usage as f32);
+            chart(ui, "Containers memory", memory, None, size);
+        });
+    });
+}
+
+fn cpu(history: &History) -> Vec<Series> {
+    let cores = history
+        .samples
+        .iter()
+        .map(|s| s.cpu_usage.len())
+        .max()
+        .unwrap_or(0);
+
+    (0..cores)
+        .map(|core| Series {
+            name: format!("cpu{core}"),
+            points: history
+                .samples
+                .iter()
+                .filter_map(|s| Some((s.timestamp, *s.cpu_usage.get(core)?)))
+                .collect(),
+        })
+        .collect()
+}
+
+fn usage(history: &History) -> Vec<Series> {
+    let series = |name: &str, value: fn(&Sample) -> f32| Series {
+        name: name.to_owned(),
+        points: history
+            .samples
+            .iter()
+            .map(|s| (s.timestamp, value(s)))
+            .collect(),
+    };
+
+    vec![
+        series("Memory", |s| s.memory_usage),
+        series("Swap", |s| s.swap_usage),
+        series("Disk", |s| s.disk_usage),
+    ]
+}
+
+fn network(history: &History) -> Vec<Series> {
+    vec![
+        Series {
+            name: "Received".to_owned(),
+            points: history
+                .samples
+                .iter()
+                .map(|s| (s.timestamp, s.network_received))
+                .collect(),
+        },
+        Series {
+            name: "Transmitted".to_owned(),
+            points: history
+                .samples
+                .iter()
+                .map(|s| (s.timestamp, s.network_transmitted))
+                .collect(),
+        },
+    ]
+}
+
+fn containers(history: &History, value: fn(&ContainerSample) -> f32) -> Vec<Series> {
+    let mut containers: BTreeMap<&str, Vec<(i64, f32)>> = BTreeMap::new();
+    for sample in &history.samples {
+        for container in &sample.containers {
+            containers
+                .entry(&container.name)
+                .or_default()
+                .push((sample.timestamp, value(container)));
+        }
+    }
+
+    containers
+        .into_iter()
+        .map(|(name, points)| Series {
+            name: name.to_owned(),
+            points,
+        })
+        .collect()
+}
+
+fn percent(value: f32) -> String {
+    format!("{value:.1}%")
+}
+
+fn size(value: f32) -> String {
+    humansize::format_size(value as u64, DECIMAL)
+}
+
+fn throughput(value: f32) -> String {
+    format!("{}/s", humansize::format_size(value as u64, DECIMAL))
+}
+
+/// Draws the series scaled to `max`, or to their largest value when unset.
+fn chart(
+    ui: &mut Ui,
+    title: &str,
+    series: Vec<Series>,
+    max: Option<f32>,
+    format: fn(f32) -> String,
+) {
+    puffin::profile_function!();
+
+    let points = || series.iter().flat_map(|s| &s.points);
+    let (Some(start), Some(end)) = (
+        points().map(|(t, _)| *t).min(),
+        points().map(|(t, _)| *t).max(),
+    ) else {
+        return;
+    };
+    let max = max
+        .unwrap_or_else(|| points().map(|(_, v)| *v).fold(0.0, f32::max))
+        .max(1.0);
+
+    ui.vertical(|ui| {
+        ui.label(RichText::new(title).color(Color32::WHITE));
+        ui.group(|ui| {
+            let (response, painter) = ui.allocate_painter(CHART_SIZE, Sense::hover());
+            let rect = response.rect;
+            painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::DARK_GRAY));
+            painter.text(
+                rect.left_top() + vec2(4.0, 2.0),
+                egui::Align2::LEFT_TOP,
+                format(max),
+                egui::FontId::monospace(10.0),
+                Color32::GRAY,
+            );
+
+            let position = |(t, v): (i64, f32)| {
+                let x = if end > start {
+                    (t - start) as f32 / (end - start) as f32
+                } else {
+                    1.0
+                };
+                pos2(
+                    rect.left() + x * rect.width(),
+                    rect.bottom() - (v / max).clamp(0.0, 1.0) * rect.height(),
+                )
+            };
+            for (s, color) in series.iter().zip(COLORS.iter().cycle()) {
+                let line: Vec<_> = s.points.iter().copied().map(position).collect();
+                painter.add(Shape::line(line, Stroke::new(1.5, *color)));
+            }
+
+            if let Some(pointer) = response.hover_pos() {
+                painter.vline(pointer.x, rect.y_range(), Stroke::new(1.0, Color32::GRAY));
+                let x = (pointer.x - rect.left()) / rect.width();
+                tooltip(
+                    ui,
+                    start + (x * (end - start) as f32) as i64,
+                    &series,
+                    format,
+                );
+            }
+
+            ui.horizontal_wrapped(|ui| {
+                ui.set_max_width(CHART_SIZE.x);
+                for (s, color) in series.iter().zip(COLORS.iter().cycle()) {
+                    let latest = s.points.last().map(|(_, v)| format(*v)).unwrap_or_default();
+                    ui.label(RichText::new(format!("{} {latest}", s.name)).color(*color));
+                }
+            });
+        });
+    });
+}
+
+/// Lists the values closest to the hovered time.
+fn tooltip(ui: &Ui, time: i64, series: &[Series], format: fn(f32) -> String) {
+    let mut text = DateTime::from_timestamp_millis(time)
+        .map(|t| t.with_timezone(&Local).format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+    for s in series {
+        if let Some((_, value)) = s.points.iter().min_by_key(|(t, _)| (t - time).abs()) {
+            text.push_str(&format!("\n{} {}", s.name, format(*value)));
+        }
+    }
+
+    egui::show_tooltip_at_pointer(ui.ctx(), ui.layer_id(), ui.id().with("hover"), |ui| {
+        ui.label(text);
+    });
+}
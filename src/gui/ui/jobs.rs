@@ -0,0 +1,124 @@
+use egui::{CollapsingHeader, Color32, RichText, ScrollArea, TextStyle, Ui};
+use tracing::error;
+
+use crate::state::job::{Job, JobRun};
+use crate::{client, update, App};
+
+impl App {
+    pub fn jobs(&self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        ui.horizontal(|ui| {
+            ui.heading(RichText::new("Jobs").color(Color32::WHITE));
+            if ui.button("⟳").clicked() {
+                self.refresh_jobs();
+            }
+        });
+
+        for job in &self.state.jobs {
+            self.job(ui, job);
+        }
+    }
+
+    fn job(&self, ui: &mut Ui, job: &Job) {
+        puffin::profile_function!();
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Name").color(Color32::WHITE));
+                    ui.label(&job.name);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Image").color(Color32::WHITE));
+                    ui.label(&job.image);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Command").color(Color32::WHITE));
+                    ui.label(&job.command);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Schedule").color(Color32::WHITE));
+                    ui.label(&job.schedule);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Next run").color(Color32::WHITE));
+                    ui.label(&job.next_run);
+                });
+                if let Some(error) = &job.definition_error {
+                    ui.label(RichText::new(error).color(Color32::RED));
+                }
+
+                for (i, run) in job.runs.iter().enumerate().rev() {
+                    job_run(ui, &format!("{}-{i}", job.name), run);
+                }
+
+                ui.horizontal(|ui| {
+                    if job.running {
+                        ui.label(RichText::new("Running").color(Color32::YELLOW));
+                    } else if ui.button("Run now").clicked() {
+                        self.run_job(job.name.clone());
+                    }
+                });
+            });
+        });
+    }
+
+    pub fn refresh_jobs(&self) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::update_jobs(config, tx).await {
+                error!("Update jobs error: {err:?}");
+            }
+        });
+    }
+
+    fn run_job(&self, name: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            let server_address = config.server_address.clone();
+            if let Err(err) = client::jobs::run_job(server_address, name).await {
+                error!("{err:?}");
+            }
+
+            if let Err(err) = update::update_jobs(config, tx).await {
+                error!("Update jobs error: {err:?}");
+            }
+        });
+    }
+}
+
+fn job_run(ui: &mut Ui, id: &str, run: &JobRun) {
+    puffin::profile_function!();
+
+    let status = if run.exit_code == 0 {
+        RichText::new(format!("{} exit 0", run.started)).color(Color32::GREEN)
+    } else {
+        RichText::new(format!("{} exit {}", run.started, run.exit_code)).color(Color32::RED)
+    };
+
+    CollapsingHeader::new(status)
+        .id_source(format!("{id}-header"))
+        .show(ui, |ui| {
+            ui.label(format!("Took {}", run.duration));
+            ScrollArea::vertical()
+                .id_source(id)
+                .max_height(400.0)
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show_rows(
+                    ui,
+                    ui.text_style_height(&TextStyle::Monospace),
+                    run.logs.len(),
+                    |ui, row_range| {
+                        for line in &run.logs[row_range.start..row_range.end] {
+                            ui.label(RichText::new(line).monospace());
+                        }
+                    },
+                );
+        });
+}
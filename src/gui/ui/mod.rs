@@ -1,31 +1,70 @@
-use egui::{CentralPanel, Color32, Context, RichText, ScrollArea, SidePanel};
+use egui::{CentralPanel, Color32, Context, RichText, ScrollArea, SidePanel, Ui};
 
-use crate::App;
+use crate::{state::Tab, App};
 
 mod compose;
 mod docker;
+mod history;
 mod info;
+mod jobs;
+mod schedule;
+mod secrets;
+mod terminal;
 
 impl App {
-    pub fn ui(&self, ctx: &Context) {
+    pub fn ui(&mut self, ctx: &Context) {
         puffin::profile_function!();
         CentralPanel::default().show(ctx, |ui| {
             ui.vertical(|ui| {
                 ScrollArea::vertical().id_source("ui").show(ui, |ui| {
                     ui.heading(RichText::new("Server manager").color(Color32::WHITE));
+                    self.tabs(ui);
                     ui.add_space(10.0);
 
-                    info::info(ui, &self.state.info);
-                    ui.add_space(10.0);
-
-                    self.docker(ui);
-                    ui.add_space(10.0);
-
-                    self.compose(ui);
+                    match self.state.tab {
+                        Tab::Overview => self.overview(ui),
+                        Tab::Scheduled => self.scheduled(ui),
+                        Tab::Jobs => self.jobs(ui),
+                    }
                 });
             });
         });
         self.log_panel(ctx);
+        self.terminal_window(ctx);
+    }
+
+    fn tabs(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.state.tab, Tab::Overview, "Overview");
+            if ui
+                .selectable_value(&mut self.state.tab, Tab::Scheduled, "Scheduled")
+                .clicked()
+            {
+                self.refresh_scheduled();
+            }
+            if ui
+                .selectable_value(&mut self.state.tab, Tab::Jobs, "Jobs")
+                .clicked()
+            {
+                self.refresh_jobs();
+            }
+        });
+    }
+
+    fn overview(&mut self, ui: &mut Ui) {
+        info::info(ui, &self.state.info);
+        ui.add_space(10.0);
+
+        history::history(ui, &self.state.history);
+        ui.add_space(10.0);
+
+        self.docker(ui);
+        ui.add_space(10.0);
+
+        self.compose(ui);
+        ui.add_space(10.0);
+
+        self.secrets(ui);
     }
 
     pub fn log_panel(&self, ctx: &Context) {
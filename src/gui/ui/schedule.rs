@@ -0,0 +1,124 @@
+use egui::{Color32, RichText, Ui};
+use tracing::error;
+
+use crate::state::{
+    compose::parse_variables,
+    schedule::{parse_schedule, ScheduledDeploy},
+};
+use crate::{client, update, App};
+
+impl App {
+    pub fn scheduled(&mut self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        ui.horizontal(|ui| {
+            ui.heading(RichText::new("Scheduled deploys").color(Color32::WHITE));
+            if ui.button("⟳").clicked() {
+                self.refresh_scheduled();
+            }
+        });
+
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Path").color(Color32::WHITE));
+                ui.text_edit_singleline(&mut self.state.schedule_path);
+                ui.label(RichText::new("When").color(Color32::WHITE))
+                    .on_hover_text("YYYY-MM-DD HH:MM or a cron expression, both in UTC");
+                ui.text_edit_singleline(&mut self.state.schedule_when);
+
+                if ui.button("Schedule").clicked() {
+                    self.schedule_deploy();
+                }
+            });
+        });
+
+        for deploy in &self.state.scheduled_deploys {
+            self.scheduled_deploy(ui, deploy);
+        }
+    }
+
+    fn scheduled_deploy(&self, ui: &mut Ui, deploy: &ScheduledDeploy) {
+        puffin::profile_function!();
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Path").color(Color32::WHITE));
+                    ui.label(&deploy.path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Schedule").color(Color32::WHITE));
+                    ui.label(&deploy.schedule);
+                });
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Next run").color(Color32::WHITE));
+                    ui.label(&deploy.next_run);
+                });
+
+                for outcome in deploy.outcomes.iter().rev() {
+                    ui.horizontal(|ui| {
+                        ui.label(&outcome.time);
+                        if outcome.success {
+                            ui.label(RichText::new("Success").color(Color32::GREEN));
+                        } else {
+                            ui.label(RichText::new("Failed").color(Color32::RED));
+                        }
+                        ui.label(&outcome.message);
+                    });
+                }
+
+                if ui.button("Cancel").clicked() {
+                    self.cancel_scheduled(deploy.id.clone());
+                }
+            });
+        });
+    }
+
+    pub fn refresh_scheduled(&self) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::update_scheduled(config, tx).await {
+                error!("Update scheduled error: {err:?}");
+            }
+        });
+    }
+
+    fn schedule_deploy(&self) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+        let path = self.state.schedule_path.clone();
+        let variables = parse_variables(&self.state.deploy_variables);
+        let schedule = parse_schedule(&self.state.schedule_when);
+
+        self.rt.spawn(async move {
+            let server_address = config.server_address.clone();
+            if let Err(err) =
+                client::schedule::schedule_deploy(server_address, path, variables, schedule).await
+            {
+                error!("{err:?}");
+            }
+
+            if let Err(err) = update::update_scheduled(config, tx).await {
+                error!("Update scheduled error: {err:?}");
+            }
+        });
+    }
+
+    fn cancel_scheduled(&self, id: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            let server_address = config.server_address.clone();
+            if let Err(err) = client::schedule::cancel_scheduled(server_address, id).await {
+                error!("{err:?}");
+            }
+
+            if let Err(err) = update::update_scheduled(config, tx).await {
+                error!("Update scheduled error: {err:?}");
+            }
+        });
+    }
+}
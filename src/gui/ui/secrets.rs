@@ -0,0 +1,86 @@
+use egui::{Color32, RichText, TextEdit, Ui};
+use tracing::error;
+
+use crate::{client, update, App};
+
+impl App {
+    pub fn secrets(&mut self, ui: &mut Ui) {
+        puffin::profile_function!();
+
+        ui.horizontal(|ui| {
+            ui.heading(RichText::new("Secrets").color(Color32::WHITE));
+            if ui.button("⟳").clicked() {
+                self.refresh_secrets();
+            }
+        });
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                for name in &self.state.secrets {
+                    ui.horizontal(|ui| {
+                        ui.label(name);
+                        if ui.button("Delete").clicked() {
+                            self.delete_secret(name.clone());
+                        }
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("Name").color(Color32::WHITE));
+                    ui.text_edit_singleline(&mut self.state.secret_name);
+                    ui.label(RichText::new("Value").color(Color32::WHITE));
+                    ui.add(TextEdit::singleline(&mut self.state.secret_value).password(true));
+
+                    if ui.button("Set").clicked() {
+                        let name = std::mem::take(&mut self.state.secret_name);
+                        let value = std::mem::take(&mut self.state.secret_value);
+                        self.set_secret(name, value);
+                    }
+                });
+            });
+        });
+    }
+
+    fn refresh_secrets(&self) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            if let Err(err) = update::update_secrets(config, tx).await {
+                error!("Update secrets error: {err:?}");
+            }
+        });
+    }
+
+    fn set_secret(&self, name: String, value: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            let server_address = config.server_address.clone();
+            if let Err(err) = client::secrets::set_secret(server_address, name, value).await {
+                error!("{err:?}");
+            }
+
+            if let Err(err) = update::update_secrets(config, tx).await {
+                error!("Update secrets error: {err:?}");
+            }
+        });
+    }
+
+    fn delete_secret(&self, name: String) {
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+
+        self.rt.spawn(async move {
+            let server_address = config.server_address.clone();
+            if let Err(err) = client::secrets::delete_secret(server_address, name).await {
+                error!("{err:?}");
+            }
+
+            if let Err(err) = update::update_secrets(config, tx).await {
+                error!("Update secrets error: {err:?}");
+            }
+        });
+    }
+}
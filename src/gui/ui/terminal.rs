@@ -0,0 +1,212 @@
+use egui::{
+    text::LayoutJob, Color32, Context, FontId, Key, RichText, ScrollArea, TextEdit, TextFormat,
+    TextStyle, Ui, Window,
+};
+use tracing::{debug, error};
+
+use crate::proto::{exec_input, ExecInput, ExecStart, TerminalSize};
+use crate::state::terminal::{Line, Terminal, TerminalColor};
+use crate::state::State;
+use crate::{update, App};
+
+const SHELL: &str = "/bin/sh";
+const CTRL_C: &[u8] = &[0x03];
+const CTRL_D: &[u8] = &[0x04];
+
+impl App {
+    pub fn open_terminal(&self, id: String, name: String) {
+        let (input_tx, input_rx) = tokio::sync::mpsc::channel(64);
+        let start = ExecInput {
+            input: Some(exec_input::Input::Start(ExecStart {
+                container_id: id.clone(),
+                command: vec![SHELL.to_owned()],
+                size: None,
+            })),
+        };
+        if let Err(err) = input_tx.try_send(start) {
+            error!("{err:?}");
+            return;
+        }
+
+        let terminal = Terminal::new(id, name, input_tx);
+        let session = terminal.session;
+        if let Err(err) = self.tx.send(Box::new(move |state: &mut State| {
+            state.terminal = Some(terminal);
+        })) {
+            error!("{err:?}");
+            return;
+        }
+
+        let config = self.config.clone();
+        let tx = self.tx.clone();
+        let task = self.rt.spawn(async move {
+            if let Err(err) = update::exec(config, session, input_rx, tx).await {
+                error!("{err:?}");
+            }
+        });
+
+        let task = task.abort_handle();
+        if let Err(err) = self.tx.send(Box::new(move |state: &mut State| {
+            match &mut state.terminal {
+                Some(terminal) if terminal.session == session => terminal.task = Some(task),
+                _ => task.abort(),
+            }
+        })) {
+            error!("{err:?}");
+        }
+    }
+
+    pub fn terminal_window(&mut self, ctx: &Context) {
+        puffin::profile_function!();
+
+        let Some(terminal) = &mut self.state.terminal else {
+            return;
+        };
+
+        let mut open = true;
+        Window::new(format!("Terminal - {}", terminal.container_name))
+            .id(egui::Id::new("terminal"))
+            .open(&mut open)
+            .default_size([800.0, 500.0])
+            .resizable(true)
+            .show(ctx, |ui| {
+                terminal_status(ui, terminal);
+                terminal_input(ui, terminal);
+                terminal_output(ui, terminal);
+            });
+
+        if !open {
+            self.state.terminal = None;
+        }
+    }
+}
+
+fn terminal_status(ui: &mut Ui, terminal: &Terminal) {
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Container").color(Color32::WHITE));
+        ui.label(&terminal.container_id);
+        if let Some(exit_code) = terminal.exit_code {
+            ui.label(RichText::new(format!("Exited with {exit_code}")).color(Color32::YELLOW));
+        }
+        if let Some(error) = &terminal.error {
+            ui.label(RichText::new(error).color(Color32::RED));
+        }
+    });
+}
+
+fn terminal_input(ui: &mut Ui, terminal: &mut Terminal) {
+    ui.horizontal(|ui| {
+        ui.add_enabled_ui(terminal.is_running(), |ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut terminal.input)
+                    .font(TextStyle::Monospace)
+                    .desired_width(400.0),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                let line = format!("{}\r", std::mem::take(&mut terminal.input));
+                send(terminal, exec_input::Input::Stdin(line.into_bytes()));
+                response.request_focus();
+            }
+            if ui.button("Ctrl-C").clicked() {
+                send(terminal, exec_input::Input::Stdin(CTRL_C.to_vec()));
+            }
+            if ui.button("Ctrl-D").clicked() {
+                send(terminal, exec_input::Input::Stdin(CTRL_D.to_vec()));
+            }
+        });
+    });
+}
+
+fn terminal_output(ui: &mut Ui, terminal: &mut Terminal) {
+    let font_id = TextStyle::Monospace.resolve(ui.style());
+    let row_height = ui.text_style_height(&TextStyle::Monospace);
+    let char_width = ui.fonts(|f| f.glyph_width(&font_id, 'M'));
+
+    let available = ui.available_size();
+    let size = (
+        (available.y / row_height).max(1.0) as u32,
+        (available.x / char_width).max(1.0) as u32,
+    );
+    if size != terminal.size && terminal.is_running() {
+        terminal.size = size;
+        send(
+            terminal,
+            exec_input::Input::Resize(TerminalSize {
+                rows: size.0,
+                columns: size.1,
+            }),
+        );
+    }
+
+    ScrollArea::vertical()
+        .id_source("terminal_output")
+        .auto_shrink([false, false])
+        .stick_to_bottom(true)
+        .show_rows(ui, row_height, terminal.lines.len(), |ui, row_range| {
+            for line in terminal.lines.range(row_range) {
+                ui.label(layout_line(line, &font_id));
+            }
+        });
+}
+
+fn send(terminal: &Terminal, input: exec_input::Input) {
+    if let Err(err) = terminal.sender.try_send(ExecInput { input: Some(input) }) {
+        debug!("Unable to send terminal input: {err:?}");
+    }
+}
+
+fn layout_line(line: &Line, font_id: &FontId) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    for span in &line.spans {
+        let color = match span.style.color {
+            Some(color) => terminal_color(color),
+            None if span.style.bold => Color32::WHITE,
+            None => Color32::LIGHT_GRAY,
+        };
+        job.append(
+            &span.text,
+            0.0,
+            TextFormat {
+                font_id: font_id.clone(),
+                color,
+                ..Default::default()
+            },
+        );
+    }
+    job
+}
+
+fn terminal_color(color: TerminalColor) -> Color32 {
+    const PALETTE: [Color32; 16] = [
+        Color32::from_rgb(0, 0, 0),
+        Color32::from_rgb(205, 49, 49),
+        Color32::from_rgb(13, 188, 121),
+        Color32::from_rgb(229, 229, 16),
+        Color32::from_rgb(36, 114, 200),
+        Color32::from_rgb(188, 63, 188),
+        Color32::from_rgb(17, 168, 205),
+        Color32::from_rgb(229, 229, 229),
+        Color32::from_rgb(102, 102, 102),
+        Color32::from_rgb(241, 76, 76),
+        Color32::from_rgb(35, 209, 139),
+        Color32::from_rgb(245, 245, 67),
+        Color32::from_rgb(59, 142, 234),
+        Color32::from_rgb(214, 112, 214),
+        Color32::from_rgb(41, 184, 219),
+        Color32::from_rgb(255, 255, 255),
+    ];
+
+    match color {
+        TerminalColor::Indexed(index @ 0..=15) => PALETTE[index as usize],
+        TerminalColor::Indexed(index @ 16..=231) => {
+            let index = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Color32::from_rgb(level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        TerminalColor::Indexed(index) => {
+            let gray = 8 + (index - 232) * 10;
+            Color32::from_rgb(gray, gray, gray)
+        }
+        TerminalColor::Rgb(r, g, b) => Color32::from_rgb(r, g, b),
+    }
+}
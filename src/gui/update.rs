@@ -1,21 +1,53 @@
 use anyhow::{Context, Result};
 use std::{
+    collections::HashMap,
+    future::Future,
+    io::Write,
     path::{Path, PathBuf},
     sync::mpsc::Sender,
     time::{Duration, Instant},
 };
+use tokio_stream::StreamExt;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+use tonic::Streaming;
 use tracing::warn;
+use uuid::Uuid;
 
-use crate::{client::info, config::Config, proto::ComposeFile, state::State};
+use crate::{
+    client::info,
+    config::Config,
+    proto::{exec_output, CommitRequest, ComposeFile, DeployReply, ExecInput},
+    state::{
+        info::docker::{
+            change::ChangeTree,
+            event::{Event, MAX_EVENTS},
+            process::ProcessList,
+        },
+        State,
+    },
+};
 
 pub type StateChangeMessage = Box<dyn FnOnce(&mut State) + Send + Sync>;
 
-pub async fn update(tx: Sender<StateChangeMessage>, config: Config) -> Result<()> {
+/// Points requested when backfilling the charts, enough to fill them at full width.
+const BACKFILL_POINTS: u32 = 600;
+
+/// Refreshes the info and the charts, which are backfilled while `history_since` is unset.
+pub async fn update(
+    tx: Sender<StateChangeMessage>,
+    config: Config,
+    history_since: Option<i64>,
+) -> Result<()> {
     let start = Instant::now();
 
     let state_change_message = update_info(config.server_address.clone()).await?;
     tx.send(state_change_message)?;
 
+    match update_history(config.server_address.clone(), history_since).await {
+        Ok(state_change_message) => tx.send(state_change_message)?,
+        Err(err) => warn!("History update failed: {err:?}"),
+    }
+
     let elapsed = Instant::now() - start;
     if elapsed > Duration::from_millis(500) {
         warn!("Update time: {elapsed:?}");
@@ -28,7 +60,15 @@ async fn update_info(server_address: String) -> Result<StateChangeMessage> {
     let info = info::get_info(server_address).await?;
 
     Ok(Box::new(move |state: &mut State| {
-        state.info = info;
+        state.info.update(info);
+    }))
+}
+
+async fn update_history(server_address: String, since: Option<i64>) -> Result<StateChangeMessage> {
+    let samples = info::get_history(server_address, since, BACKFILL_POINTS).await?;
+
+    Ok(Box::new(move |state: &mut State| {
+        state.history.extend(samples);
     }))
 }
 
@@ -47,6 +87,30 @@ pub async fn update_compose_diffs(config: Config, tx: Sender<StateChangeMessage>
     }))?)
 }
 
+pub async fn update_secrets(config: Config, tx: Sender<StateChangeMessage>) -> Result<()> {
+    let secrets = crate::client::secrets::list_secrets(config.server_address).await?;
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.secrets = secrets;
+    }))?)
+}
+
+pub async fn update_scheduled(config: Config, tx: Sender<StateChangeMessage>) -> Result<()> {
+    let scheduled_deploys = crate::client::schedule::list_scheduled(config.server_address).await?;
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.scheduled_deploys = scheduled_deploys;
+    }))?)
+}
+
+pub async fn update_jobs(config: Config, tx: Sender<StateChangeMessage>) -> Result<()> {
+    let jobs = crate::client::jobs::list_jobs(config.server_address).await?;
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.jobs = jobs;
+    }))?)
+}
+
 fn gather_files(root_path: &PathBuf, path: &Path, files: &mut Vec<ComposeFile>) -> Result<()> {
     for dir_entry in path.read_dir()? {
         let dir_entry = dir_entry?;
@@ -69,3 +133,307 @@ fn gather_files(root_path: &PathBuf, path: &Path, files: &mut Vec<ComposeFile>)
 
     Ok(())
 }
+
+pub async fn deploy(
+    config: Config,
+    path: PathBuf,
+    variables: HashMap<String, String>,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    tx.send(Box::new(|state: &mut State| state.deploy_output.clear()))?;
+
+    let stream = crate::client::compose::deploy(config.server_address, path, variables).await?;
+    handle_deploy_stream(stream, tx).await
+}
+
+pub async fn undeploy(
+    config: Config,
+    path: PathBuf,
+    variables: HashMap<String, String>,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    tx.send(Box::new(|state: &mut State| state.deploy_output.clear()))?;
+
+    let stream = crate::client::compose::undeploy(config.server_address, path, variables).await?;
+    handle_deploy_stream(stream, tx).await
+}
+
+async fn handle_deploy_stream(
+    mut stream: Streaming<DeployReply>,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    while let Some(reply) = stream.next().await {
+        let line = match reply {
+            Ok(reply) => reply.output,
+            Err(status) => format!("Failed: {}", status.message()),
+        };
+
+        tx.send(Box::new(move |state: &mut State| {
+            state.deploy_output.push(line)
+        }))?;
+    }
+
+    Ok(())
+}
+
+/// Runs an exec session, its output only reaches the terminal with the matching `session`.
+pub async fn exec(
+    config: Config,
+    session: Uuid,
+    input: tokio::sync::mpsc::Receiver<ExecInput>,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let mut stream = match crate::client::docker::exec(input, config.server_address).await {
+        Ok(stream) => stream,
+        Err(err) => {
+            let message = error_message(&err);
+            tx.send(Box::new(move |state: &mut State| {
+                match &mut state.terminal {
+                    Some(terminal) if terminal.session == session => terminal.error = Some(message),
+                    _ => {}
+                }
+            }))?;
+            return Err(err);
+        }
+    };
+
+    while let Some(output) = stream.next().await {
+        tx.send(Box::new(move |state: &mut State| {
+            let Some(terminal) = state.terminal.as_mut().filter(|t| t.session == session) else {
+                return;
+            };
+
+            match output {
+                Ok(output) => match output.output {
+                    Some(exec_output::Output::Stdout(data)) => terminal.feed(&data),
+                    Some(exec_output::Output::ExitCode(code)) => terminal.exit_code = Some(code),
+                    None => {}
+                },
+                Err(status) => terminal.error = Some(status.message().to_owned()),
+            }
+        }))?;
+    }
+
+    Ok(())
+}
+
+pub async fn download_archive(
+    config: Config,
+    id: String,
+    path: String,
+    destination: PathBuf,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let result = async {
+        let stream = crate::client::docker::download_archive(
+            id.clone(),
+            path.clone(),
+            config.server_address,
+        )
+        .await?;
+
+        // Unpacked on the blocking pool while the chunks are still arriving.
+        let chunks = stream.map(|chunk| {
+            chunk
+                .map(|chunk| std::io::Cursor::new(chunk.data))
+                .map_err(std::io::Error::other)
+        });
+        let archive = SyncIoBridge::new(StreamReader::new(chunks));
+        let target = destination.clone();
+        tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&target)?;
+            tar::Archive::new(archive).unpack(&target)
+        })
+        .await??;
+        anyhow::Ok(format!("Downloaded {path} to {destination:?}"))
+    }
+    .await;
+
+    send_transfer_status(&tx, id, result)
+}
+
+pub async fn upload_file(
+    config: Config,
+    id: String,
+    source: PathBuf,
+    destination: String,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let result = async {
+        let name = source.file_name().context("source has no file name")?;
+        let mut builder = tar::Builder::new(Vec::new());
+        if source.is_dir() {
+            builder.append_dir_all(name, &source)?;
+        } else {
+            builder.append_path_with_name(&source, name)?;
+        }
+        let archive = builder.into_inner()?;
+
+        crate::client::docker::upload_archive(
+            id.clone(),
+            destination.clone(),
+            archive,
+            config.server_address,
+        )
+        .await?;
+        anyhow::Ok(format!("Uploaded {source:?} to {destination}"))
+    }
+    .await;
+
+    send_transfer_status(&tx, id, result)
+}
+
+pub async fn update_processes(
+    config: Config,
+    id: String,
+    ps_args: String,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let processes = crate::client::docker::top(id.clone(), ps_args, config.server_address)
+        .await
+        .map(ProcessList::from)
+        .map_err(|err| error_message(&err));
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        let view = state.container_views.entry(id).or_default();
+        let previous = view.processes.as_ref().and_then(|p| p.as_ref().ok());
+        view.processes = Some(processes.map(|p| p.sorted_like(previous)));
+    }))?)
+}
+
+pub async fn update_changes(
+    config: Config,
+    id: String,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let changes = crate::client::docker::changes(id.clone(), config.server_address)
+        .await
+        .map(ChangeTree::from)
+        .map_err(|err| error_message(&err));
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.container_views.entry(id).or_default().changes = Some(changes);
+    }))?)
+}
+
+pub async fn commit_container(
+    config: Config,
+    id: String,
+    repository: String,
+    tag: String,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let request = CommitRequest {
+        id: id.clone(),
+        repository: repository.clone(),
+        tag: tag.clone(),
+        comment: String::new(),
+        pause: true,
+    };
+    let status = match crate::client::docker::commit(request, config.server_address).await {
+        Ok(image_id) => format!("Committed {repository}:{tag} ({image_id})"),
+        Err(err) => format!("Failed: {}", error_message(&err)),
+    };
+
+    send_snapshot_status(&tx, id, status)
+}
+
+pub async fn export_container(
+    config: Config,
+    id: String,
+    destination: PathBuf,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let result = async {
+        let mut stream = crate::client::docker::export(id.clone(), config.server_address).await?;
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&destination)?);
+
+        let mut size = 0;
+        while let Some(chunk) = stream.next().await {
+            let data = chunk?.data;
+            size += data.len();
+            file.write_all(&data)?;
+        }
+        file.flush()?;
+
+        anyhow::Ok(size)
+    }
+    .await;
+
+    let status = match result {
+        Ok(size) => format!(
+            "Exported {} to {destination:?}",
+            humansize::format_size(size, humansize::BINARY)
+        ),
+        Err(err) => {
+            if let Err(err) = std::fs::remove_file(&destination) {
+                warn!("Unable to remove partial export {destination:?}: {err:?}");
+            }
+            format!("Failed: {}", error_message(&err))
+        }
+    };
+
+    send_snapshot_status(&tx, id, status)
+}
+
+fn send_snapshot_status(tx: &Sender<StateChangeMessage>, id: String, status: String) -> Result<()> {
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.container_views.entry(id).or_default().snapshot_status = Some(status);
+    }))?)
+}
+
+/// Runs a container action and shows its error, if any, on the container card.
+pub async fn container_action(
+    id: String,
+    action: impl Future<Output = Result<()>>,
+    tx: Sender<StateChangeMessage>,
+) -> Result<()> {
+    let action_error = action.await.err().map(|err| {
+        warn!("Container action failed: {err:?}");
+        error_message(&err)
+    });
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.container_views.entry(id).or_default().action_error = action_error;
+    }))?)
+}
+
+/// The server's message for failed calls, without the gRPC status decoration.
+fn error_message(err: &anyhow::Error) -> String {
+    match err.downcast_ref::<tonic::Status>() {
+        Some(status) => status.message().to_owned(),
+        None => err.to_string(),
+    }
+}
+
+fn send_transfer_status(
+    tx: &Sender<StateChangeMessage>,
+    id: String,
+    result: Result<String>,
+) -> Result<()> {
+    let status = match result {
+        Ok(status) => status,
+        Err(err) => format!("Failed: {}", error_message(&err)),
+    };
+
+    Ok(tx.send(Box::new(move |state: &mut State| {
+        state.container_views.entry(id).or_default().transfer_status = Some(status);
+    }))?)
+}
+
+pub async fn stream_events(server_address: String, tx: Sender<StateChangeMessage>) -> Result<()> {
+    let mut stream = crate::client::docker::events(server_address).await?;
+
+    while let Some(event) = stream.next().await {
+        let event = event?;
+        tx.send(Box::new(move |state: &mut State| {
+            state.info.docker_info.apply(&event);
+            state.docker_events.push_back(Event::from(&event));
+            while state.docker_events.len() > MAX_EVENTS {
+                state.docker_events.pop_front();
+            }
+        }))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use stack::StackDefinition;
+
+pub mod stack;
+pub mod template;
+
+/// A deployable file in the compose directory.
+pub enum Definition {
+    Service(Box<ServiceDefinition>),
+    Stack(StackDefinition),
+}
+
+impl Definition {
+    /// Files with a `services` table are stacks, everything else is a single service.
+    /// Variables are substituted in string values after parsing.
+    pub fn load(path: &Path, variables: &HashMap<String, String>) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(&std::fs::read_to_string(path)?)
+            .with_context(|| format!("invalid definition {path:?}"))?;
+        template::substitute(&mut table, variables, path)?;
+
+        if table.contains_key("services") {
+            let stack = table
+                .try_into()
+                .with_context(|| format!("invalid stack definition {path:?}"))?;
+            Ok(Self::Stack(stack))
+        } else {
+            let service = table
+                .try_into()
+                .with_context(|| format!("invalid service definition {path:?}"))?;
+            Ok(Self::Service(service))
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PortMapping {
+    pub protocol: String,
+    pub host_ip: String,
+    pub host_port: String,
+    pub container_port: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ServiceDefinition {
+    pub image: String,
+    pub tag: String,
+    pub container_name: String,
+    pub command: Option<String>,
+    pub binds: Option<Vec<String>>,
+    pub ports: Vec<PortMapping>,
+    pub build: Option<BuildDefinition>,
+    pub health: Option<HealthCheck>,
+    pub env: Option<BTreeMap<String, String>>,
+    pub files: Option<Vec<FileMount>>,
+    #[serde(default)]
+    pub pre_deploy: Vec<Hook>,
+    #[serde(default)]
+    pub post_deploy: Vec<Hook>,
+}
+
+/// A command run around a deploy, either in a one-off container or inside
+/// the newly started container. One-off containers default to the service image.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Hook {
+    Container {
+        image: Option<String>,
+        command: Vec<String>,
+    },
+    Exec {
+        command: Vec<String>,
+    },
+}
+
+/// A file placed into the container before it starts.
+/// `source` has to reference a secret as `secret:NAME`. Files are owned by root and
+/// readable by everyone unless `mode`, `uid` and `gid` say otherwise, e.g. `mode = 0o400`
+/// with the uid of the user the image runs as.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FileMount {
+    pub source: String,
+    pub target: PathBuf,
+    #[serde(default = "default_file_mode")]
+    pub mode: u32,
+    #[serde(default)]
+    pub uid: u64,
+    #[serde(default)]
+    pub gid: u64,
+}
+
+fn default_file_mode() -> u32 {
+    0o444
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BuildDefinition {
+    pub context: PathBuf,
+    #[serde(default = "default_dockerfile")]
+    pub dockerfile: String,
+}
+
+fn default_dockerfile() -> String {
+    "Dockerfile".to_string()
+}
+
+/// Gates a deploy on the new container's health.
+/// Durations are in seconds.
+#[derive(Deserialize, Clone, Debug)]
+pub struct HealthCheck {
+    #[serde(default = "default_health_timeout")]
+    pub timeout: u64,
+    #[serde(default = "default_grace_period")]
+    pub grace_period: u64,
+}
+
+fn default_health_timeout() -> u64 {
+    60
+}
+
+fn default_grace_period() -> u64 {
+    10
+}
@@ -0,0 +1,77 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use super::ServiceDefinition;
+
+/// A group of services sharing networks and volumes.
+#[derive(Deserialize, Clone, Debug)]
+pub struct StackDefinition {
+    #[serde(default)]
+    pub networks: Vec<String>,
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    pub services: BTreeMap<String, StackService>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct StackService {
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(flatten)]
+    pub service: ServiceDefinition,
+}
+
+impl StackDefinition {
+    /// Returns the service names ordered so that every service comes after its dependencies.
+    pub fn deploy_order(&self) -> Result<Vec<String>> {
+        let mut order = Vec::new();
+        let mut done = HashSet::new();
+        let mut path = Vec::new();
+
+        for name in self.services.keys() {
+            self.visit(name, &mut path, &mut done, &mut order)?;
+        }
+
+        Ok(order)
+    }
+
+    fn visit(
+        &self,
+        name: &str,
+        path: &mut Vec<String>,
+        done: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        if done.contains(name) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|n| n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            bail!("dependency cycle between services: {}", cycle.join(" -> "));
+        }
+
+        let Some(service) = self.services.get(name) else {
+            match path.last() {
+                Some(dependent) => {
+                    bail!("service '{dependent}' depends on unknown service '{name}'")
+                }
+                None => bail!("unknown service '{name}'"),
+            }
+        };
+
+        path.push(name.to_string());
+        for dependency in &service.depends_on {
+            self.visit(dependency, path, done, order)?;
+        }
+        path.pop();
+
+        done.insert(name.to_string());
+        order.push(name.to_string());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// Name of the server-side variables file in the compose directory.
+pub const ENV_FILE: &str = ".env";
+
+/// Reads `KEY=VALUE` lines, ignoring blank lines and `#` comments.
+pub fn load_env_file(path: &Path) -> Result<HashMap<String, String>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let mut variables = HashMap::new();
+    for (i, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .with_context(|| format!("invalid line {} in {path:?}", i + 1))?;
+
+        variables.insert(key.trim().to_string(), unquote(value.trim()).to_string());
+    }
+
+    Ok(variables)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+
+    value
+}
+
+/// Replaces `${VAR}` and `${VAR:-default}` in the string values of `table`, `$$` yields a
+/// literal `$`. Keys and comments stay as written, values are never parsed as TOML again.
+/// `path` is only used for error messages.
+pub fn substitute(
+    table: &mut toml::Table,
+    variables: &HashMap<String, String>,
+    path: &Path,
+) -> Result<()> {
+    let mut unresolved = BTreeSet::new();
+    for (_, value) in table.iter_mut() {
+        substitute_value(value, variables, &mut unresolved, path)?;
+    }
+
+    if !unresolved.is_empty() {
+        let names: Vec<_> = unresolved.into_iter().collect();
+        bail!("unresolved variables {} in {path:?}", names.join(", "));
+    }
+
+    Ok(())
+}
+
+fn substitute_value(
+    value: &mut toml::Value,
+    variables: &HashMap<String, String>,
+    unresolved: &mut BTreeSet<String>,
+    path: &Path,
+) -> Result<()> {
+    match value {
+        toml::Value::String(content) => *content = expand(content, variables, unresolved, path)?,
+        toml::Value::Array(values) => {
+            for value in values {
+                substitute_value(value, variables, unresolved, path)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                substitute_value(value, variables, unresolved, path)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Expands a single string, collecting the names without a value in `unresolved`.
+fn expand(
+    content: &str,
+    variables: &HashMap<String, String>,
+    unresolved: &mut BTreeSet<String>,
+    path: &Path,
+) -> Result<String> {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if let Some(after) = after.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+            continue;
+        }
+
+        let Some(expression) = after.strip_prefix('{') else {
+            result.push('$');
+            rest = after;
+            continue;
+        };
+
+        let Some(end) = expression.find('}') else {
+            bail!("unterminated variable in {path:?}");
+        };
+
+        let (name, default) = match expression[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&expression[..end], None),
+        };
+
+        match (variables.get(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => result.push_str(default),
+            (Some(value), _) => result.push_str(value),
+            (None, Some(default)) => result.push_str(default),
+            (None, None) => {
+                unresolved.insert(name.to_string());
+            }
+        }
+
+        rest = &expression[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
@@ -8,6 +8,36 @@ pub struct Config {
     pub address: SocketAddr,
     pub docker_compose_path: PathBuf,
     pub update_interval: u64,
+    /// `unix:///path` or `tcp://host:port`, falls back to `DOCKER_HOST` and the default socket.
+    #[serde(default)]
+    pub docker_host: Option<String>,
+    /// Client certificates for a daemon exposed over TLS, usually on `tcp://host:2376`.
+    #[serde(default)]
+    pub docker_tls: Option<DockerTls>,
+    #[serde(default = "default_secrets_path")]
+    pub secrets_path: PathBuf,
+    #[serde(default = "default_schedule_path")]
+    pub schedule_path: PathBuf,
+    #[serde(default = "default_archive_size_limit")]
+    pub max_download_size: u64,
+    #[serde(default = "default_archive_size_limit")]
+    pub max_upload_size: u64,
+    /// Container exports are streamed and unlimited unless set.
+    #[serde(default)]
+    pub max_export_size: Option<u64>,
+    /// Number of info samples kept for `GetHistory`, one per update interval.
+    #[serde(default = "default_history_length")]
+    pub history_length: usize,
+    /// Keeps the history across restarts when set.
+    #[serde(default)]
+    pub history_path: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DockerTls {
+    pub ca_path: PathBuf,
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
 }
 
 impl Config {
@@ -16,3 +46,19 @@ impl Config {
         Ok(config)
     }
 }
+
+fn default_secrets_path() -> PathBuf {
+    "secrets".into()
+}
+
+fn default_schedule_path() -> PathBuf {
+    "schedule.json".into()
+}
+
+fn default_archive_size_limit() -> u64 {
+    100 * 1024 * 1024
+}
+
+fn default_history_length() -> usize {
+    3600
+}
@@ -0,0 +1,357 @@
+use std::{
+    fmt,
+    future::Future,
+    io,
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use anyhow::{bail, Context as _, Result};
+use http_body_util::{combinators::BoxBody, BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    Method, Request, Response, Uri,
+};
+use hyper_util::{
+    client::legacy::{
+        connect::{Connected, Connection},
+        Client,
+    },
+    rt::{TokioExecutor, TokioIo},
+};
+use prost::bytes::Buf;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpStream, UnixStream},
+};
+use tokio_rustls::{client::TlsStream, rustls::pki_types::ServerName, TlsConnector};
+use tower_service::Service;
+
+use crate::config::DockerTls;
+
+use super::{
+    error::{Error, ErrorResponse},
+    tls,
+};
+
+pub const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+const PODMAN_SOCKET: &str = "/run/podman/podman.sock";
+const RUNTIME_DIR_ENV: &str = "XDG_RUNTIME_DIR";
+/// Newest Engine API version this client is built against.
+pub const MAX_API_VERSION: &str = "1.47";
+const DOCKER_HOST_ENV: &str = "DOCKER_HOST";
+
+/// Where the Docker daemon listens.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+impl Endpoint {
+    /// Parses `unix:///path`, `tcp://host:port` or a plain socket path.
+    pub fn parse(host: &str) -> Result<Self> {
+        if let Some(path) = host.strip_prefix("unix://") {
+            return Ok(Self::Unix(path.into()));
+        }
+
+        if let Some(address) = host.strip_prefix("tcp://") {
+            let address = address.trim_end_matches('/');
+            if address.is_empty() {
+                bail!("missing address in docker host {host:?}");
+            }
+            return Ok(Self::Tcp(address.to_owned()));
+        }
+
+        if host.contains("://") {
+            bail!("unsupported docker host {host:?}");
+        }
+
+        Ok(Self::Unix(host.into()))
+    }
+
+    /// Uses the configured host, then `DOCKER_HOST`, then the first Docker or
+    /// Podman socket that exists.
+    pub fn resolve(configured: Option<&str>) -> Result<Self> {
+        if let Some(host) = configured {
+            return Self::parse(host);
+        }
+
+        match std::env::var(DOCKER_HOST_ENV) {
+            Ok(host) if !host.is_empty() => {
+                Self::parse(&host).with_context(|| format!("invalid {DOCKER_HOST_ENV}"))
+            }
+            _ => Ok(Self::Unix(discover_socket())),
+        }
+    }
+
+    /// Authority used in request URIs, unix sockets ignore it when connecting.
+    fn authority(&self) -> &str {
+        match self {
+            Self::Unix(_) => "docker",
+            Self::Tcp(address) => address,
+        }
+    }
+}
+
+/// Docker's socket, the rootful Podman socket and the rootless Podman socket of
+/// the current user, falling back to Docker's if none exist.
+fn discover_socket() -> PathBuf {
+    let mut candidates = vec![PathBuf::from(DEFAULT_SOCKET), PathBuf::from(PODMAN_SOCKET)];
+    if let Some(runtime_dir) = std::env::var_os(RUNTIME_DIR_ENV) {
+        candidates.push(PathBuf::from(runtime_dir).join("podman/podman.sock"));
+    }
+
+    candidates
+        .into_iter()
+        .find(|path| path.exists())
+        .unwrap_or_else(|| DEFAULT_SOCKET.into())
+}
+
+/// Connects every request to the configured endpoint, regardless of the URI host.
+#[derive(Clone)]
+struct Connector {
+    endpoint: Endpoint,
+    tls: Option<(TlsConnector, ServerName<'static>)>,
+}
+
+impl fmt::Debug for Connector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connector")
+            .field("endpoint", &self.endpoint)
+            .field("tls", &self.tls.is_some())
+            .finish()
+    }
+}
+
+impl Service<Uri> for Connector {
+    type Response = TokioIo<DockerStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let endpoint = self.endpoint.clone();
+        let tls = self.tls.clone();
+        Box::pin(async move {
+            let stream = match (endpoint, tls) {
+                (Endpoint::Unix(path), _) => DockerStream::Unix(UnixStream::connect(path).await?),
+                (Endpoint::Tcp(address), None) => {
+                    DockerStream::Tcp(TcpStream::connect(address).await?)
+                }
+                (Endpoint::Tcp(address), Some((connector, server_name))) => {
+                    let stream = TcpStream::connect(address).await?;
+                    DockerStream::Tls(Box::new(connector.connect(server_name, stream).await?))
+                }
+            };
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+enum DockerStream {
+    Unix(UnixStream),
+    Tcp(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Connection for DockerStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for DockerStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for DockerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Body of a request, most are sent [`Full`] and only uploads are streamed.
+pub type RequestBody = BoxBody<Bytes, BoxError>;
+
+/// Docker Engine API client sharing one connection pool, cheap to clone.
+#[derive(Clone, Debug)]
+pub struct DockerClient {
+    client: Client<Connector, RequestBody>,
+    endpoint: Endpoint,
+    api_version: String,
+}
+
+impl DockerClient {
+    /// Creates a client for `endpoint`, using client certificates when `tls` is set.
+    pub fn new(endpoint: Endpoint, tls: Option<&DockerTls>) -> Result<Self> {
+        let tls = match (&endpoint, tls) {
+            (_, None) => None,
+            (Endpoint::Tcp(address), Some(config)) => {
+                Some((tls::connector(config)?, tls::server_name(address)?))
+            }
+            (Endpoint::Unix(_), Some(_)) => bail!("docker tls requires a tcp:// host"),
+        };
+
+        let connector = Connector {
+            endpoint: endpoint.clone(),
+            tls,
+        };
+        Ok(Self {
+            client: Client::builder(TokioExecutor::new()).build(connector),
+            endpoint,
+            api_version: MAX_API_VERSION.to_owned(),
+        })
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// API version used for requests, see [`DockerClient::negotiate_api_version`].
+    pub fn api_version(&self) -> &str {
+        &self.api_version
+    }
+
+    pub(super) fn set_api_version(&mut self, api_version: String) {
+        self.api_version = api_version;
+    }
+
+    /// Builds a request for an API path such as `/containers/json`.
+    pub(super) fn builder(&self, method: Method, path: &str) -> hyper::http::request::Builder {
+        self.unversioned(method, &format!("/v{}{}", self.api_version, path))
+    }
+
+    /// Builds a request without the API version prefix, which every daemon accepts.
+    pub(super) fn unversioned(&self, method: Method, path: &str) -> hyper::http::request::Builder {
+        let uri = format!("http://{}{}", self.endpoint.authority(), path);
+        Request::builder().method(method).uri(uri)
+    }
+
+    /// Sends a request without looking at the response status.
+    pub(super) async fn execute(&self, req: Request<Full<Bytes>>) -> Result<Response<Incoming>> {
+        self.execute_streamed(req.map(|body| body.map_err(|never| match never {}).boxed()))
+            .await
+    }
+
+    /// Like [`DockerClient::execute`] with a body that is produced while sending.
+    pub(super) async fn execute_streamed(
+        &self,
+        req: Request<RequestBody>,
+    ) -> Result<Response<Incoming>> {
+        self.client
+            .request(req)
+            .await
+            .map_err(|err| Error::Unavailable(format!("{err:#}")).into())
+    }
+
+    /// Sends a request and turns error statuses into errors.
+    pub(super) async fn send(&self, req: Request<Full<Bytes>>) -> Result<Response<Incoming>> {
+        check(self.execute(req).await?).await
+    }
+
+    pub(super) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let req = self.builder(Method::GET, path).body(Full::default())?;
+        json(self.send(req).await?).await
+    }
+
+    pub(super) async fn post<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let req = self.builder(Method::POST, path).body(Full::default())?;
+        json(self.send(req).await?).await
+    }
+
+    /// Sends a request without a body, discarding the response. Docker answers
+    /// 304 when the container already is in the requested state, which is fine.
+    pub(super) async fn call(&self, method: Method, path: &str) -> Result<()> {
+        let req = self.builder(method, path).body(Full::default())?;
+        match self.send(req).await {
+            Ok(res) => {
+                res.collect().await?;
+                Ok(())
+            }
+            Err(err) if matches!(err.downcast_ref(), Some(Error::NotModified)) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(super) async fn post_json<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        let req = self
+            .builder(Method::POST, path)
+            .header("Content-Type", "application/json")
+            .body(Full::from(serde_json::to_string(body)?))?;
+        json(self.send(req).await?).await
+    }
+}
+
+/// Returns the response for success statuses, otherwise the decoded daemon error.
+pub(super) async fn check(res: Response<Incoming>) -> Result<Response<Incoming>> {
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+
+    let body = res
+        .collect()
+        .await
+        .map_err(|err| Error::Unavailable(format!("{err:#}")))?
+        .to_bytes();
+    let message = match serde_json::from_slice::<ErrorResponse>(&body) {
+        Ok(error) => error.message,
+        Err(_) => String::from_utf8_lossy(&body).trim().to_owned(),
+    };
+    Err(Error::from_status(status, message).into())
+}
+
+pub(super) async fn json<T: DeserializeOwned>(res: Response<Incoming>) -> Result<T> {
+    let body = res
+        .collect()
+        .await
+        .map_err(|err| Error::Unavailable(format!("{err:#}")))?
+        .aggregate();
+    serde_json::from_reader(body.reader()).map_err(|err| Error::Decode(err.to_string()).into())
+}
@@ -3,31 +3,29 @@ use std::collections::HashMap;
 use std::io::BufRead;
 use tracing::debug;
 
-use anyhow::bail;
 use anyhow::Result;
 use http_body_util::BodyExt;
-use http_body_util::Full;
-use hyper::body::Bytes;
-use hyper_util::client::legacy::Client;
-use hyperlocal::UnixClientExt;
-use hyperlocal::{UnixConnector, Uri};
-use prost::bytes::Buf;
+use http_body_util::{combinators::BoxBody, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::Method;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::Deserialize;
+use tokio::sync::mpsc::Receiver;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 
-use crate::docker::Error;
 use crate::proto;
 
-use super::DOCKER_SOCK;
+use super::client::{self, BoxError, DockerClient};
 
 #[derive(Deserialize, Debug)]
 pub struct Port {
     #[serde(rename = "PrivatePort")]
     private_port: i64,
 
-    #[serde(rename = "PublicPort")]
+    #[serde(rename = "PublicPort", default)]
     public_port: i64,
 
-    #[serde(rename = "Type")]
+    #[serde(rename = "Type", default, deserialize_with = "super::null_as_default")]
     port_type: String,
 }
 
@@ -46,186 +44,500 @@ pub struct Container {
     #[serde(rename = "Id")]
     pub id: String,
 
-    #[serde(rename = "Names")]
+    #[serde(rename = "Names", default, deserialize_with = "super::null_as_default")]
     pub names: Vec<String>,
 
     #[serde(rename = "Image")]
     pub image: String,
 
-    #[serde(rename = "Command")]
+    #[serde(
+        rename = "Command",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
     pub command: String,
 
-    #[serde(rename = "Created")]
+    #[serde(rename = "Created", default)]
     pub created: i64,
 
-    #[serde(rename = "Ports")]
+    #[serde(rename = "Ports", default, deserialize_with = "super::null_as_default")]
     pub ports: Vec<Port>,
 
-    #[serde(rename = "Status")]
+    #[serde(
+        rename = "Status",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
     pub status: String,
+
+    #[serde(rename = "State", default, deserialize_with = "super::null_as_default")]
+    pub state: String,
 }
 
-pub async fn list() -> Result<Vec<Container>> {
-    let url = Uri::new(DOCKER_SOCK, "/v1.47/containers/json?all=true").into();
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
+#[derive(Debug, Default)]
+pub struct RemoveOptions {
+    pub force: bool,
+    pub volumes: bool,
+}
 
-    let res = client.get(url).await?;
-    if res.status() != 200 {
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("{error:?}")
-    }
+#[derive(Serialize, Clone, Debug)]
+pub struct PortBinding {
+    #[serde(rename = "HostIp")]
+    pub host_ip: String,
 
-    let body = res.collect().await?.aggregate();
-    Ok(serde_json::from_reader(body.reader())?)
+    #[serde(rename = "HostPort")]
+    pub host_port: String,
 }
 
-pub async fn start(id: &str) -> Result<()> {
-    let url = Uri::new(DOCKER_SOCK, &format!("/v1.47/containers/{}/start", id));
-    let req = hyper::Request::builder()
-        .uri(url)
-        .method("POST")
-        .body(Full::from(""))?;
+#[derive(Serialize, Clone, Debug)]
+pub struct HostConfig {
+    #[serde(rename = "PortBindings")]
+    pub port_bindings: HashMap<String, Vec<PortBinding>>,
 
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-    let res = client.request(req).await?;
-    if res.status() != 204 && res.status() != 304 {
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("{error:?}")
-    }
+    #[serde(rename = "Binds")]
+    pub binds: Option<Vec<String>>,
+}
 
-    Ok(())
+#[derive(Serialize, Clone, Debug)]
+pub struct ContainerCreationBody {
+    #[serde(rename = "Image")]
+    pub image: String,
+
+    #[serde(rename = "Cmd")]
+    pub command: Option<Vec<String>>,
+
+    #[serde(rename = "Env")]
+    pub env: Option<Vec<String>>,
+
+    #[serde(rename = "HostConfig")]
+    pub host_config: HostConfig,
+
+    #[serde(rename = "NetworkingConfig")]
+    pub networking_config: Option<NetworkingConfig>,
 }
 
-pub async fn stop(id: &str) -> Result<()> {
-    let url = Uri::new(DOCKER_SOCK, &format!("/v1.47/containers/{}/stop", id));
-    let req = hyper::Request::builder()
-        .uri(url)
-        .method("POST")
-        .body(Full::from(""))?;
+#[derive(Serialize, Clone, Debug)]
+pub struct EndpointSettings {
+    #[serde(rename = "Aliases")]
+    pub aliases: Vec<String>,
+}
 
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-    let res = client.request(req).await?;
-    if res.status() != 204 && res.status() != 304 {
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("{error:?}")
-    }
+#[derive(Serialize, Clone, Debug)]
+pub struct NetworkingConfig {
+    #[serde(rename = "EndpointsConfig")]
+    pub endpoints_config: HashMap<String, EndpointSettings>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ContainerCreationResponse {
+    #[serde(rename = "Id")]
+    pub id: String,
+}
 
-    Ok(())
+#[derive(Deserialize, Debug)]
+pub struct Health {
+    #[serde(
+        rename = "Status",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    pub status: String,
 }
 
-pub async fn remove(id: &str) -> Result<()> {
-    let url = Uri::new(DOCKER_SOCK, &format!("/v1.47/containers/{}", id));
-    let req = hyper::Request::builder()
-        .uri(url)
-        .method("DELETE")
-        .body(Full::from(""))?;
+#[derive(Deserialize, Debug)]
+pub struct ContainerState {
+    #[serde(rename = "Status")]
+    pub status: String,
 
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-    let res = client.request(req).await?;
-    if res.status() != 204 {
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("{error:?}")
-    }
+    #[serde(rename = "Running")]
+    pub running: bool,
+
+    #[serde(rename = "ExitCode", default)]
+    pub exit_code: i64,
 
-    Ok(())
+    #[serde(rename = "Health")]
+    pub health: Option<Health>,
 }
 
-pub async fn logs(id: &str) -> Result<Vec<String>> {
-    let url = Uri::new(
-        DOCKER_SOCK,
-        &format!(
-            "/v1.47/containers/{}/logs?stderr=true&stdout=true&timestamps=true&tail=1000",
-            id
-        ),
-    );
+#[derive(Deserialize, Debug)]
+pub struct ContainerInspection {
+    #[serde(rename = "Id")]
+    pub id: String,
 
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-    let res = client.get(url.into()).await?;
+    #[serde(rename = "State")]
+    pub state: ContainerState,
+}
 
-    if res.status() != 200 {
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("{error:?}")
-    }
+#[derive(Deserialize, Debug)]
+struct WaitResponse {
+    #[serde(rename = "StatusCode")]
+    status_code: i64,
+}
 
-    let body = res.collect().await?.aggregate();
-    let reader = body.reader();
+#[derive(Deserialize, Debug)]
+pub struct Top {
+    #[serde(
+        rename = "Titles",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    pub titles: Vec<String>,
+
+    #[serde(
+        rename = "Processes",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    pub processes: Vec<Vec<String>>,
+}
 
-    let mut lines = vec![];
-    for l in reader.lines() {
-        match l {
-            Ok(line) => lines.push(line),
-            Err(err) => debug!("Skipping log line: {err:?}"),
+impl From<Top> for proto::ProcessList {
+    fn from(t: Top) -> Self {
+        Self {
+            titles: t.titles,
+            processes: t
+                .processes
+                .into_iter()
+                .map(|values| proto::ProcessRow { values })
+                .collect(),
         }
     }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Change {
+    #[serde(rename = "Path")]
+    pub path: String,
 
-    Ok(lines)
+    #[serde(rename = "Kind")]
+    pub kind: i32,
 }
 
-#[derive(Serialize, Debug)]
-pub struct PortBinding {
-    #[serde(rename = "HostIp")]
-    pub host_ip: String,
+impl From<Change> for proto::FilesystemChange {
+    fn from(c: Change) -> Self {
+        Self {
+            path: c.path,
+            kind: c.kind,
+        }
+    }
+}
 
-    #[serde(rename = "HostPort")]
-    pub host_port: String,
+/// A one-shot resource usage sample, CPU counters are cumulative nanoseconds.
+#[derive(Deserialize, Debug, Default)]
+pub struct Stats {
+    #[serde(default)]
+    pub cpu_stats: CpuStats,
+
+    #[serde(default)]
+    pub memory_stats: MemoryStats,
 }
 
-#[derive(Serialize, Debug)]
-pub struct HostConfig {
-    #[serde(rename = "PortBindings")]
-    pub port_bindings: HashMap<String, Vec<PortBinding>>,
+#[derive(Deserialize, Debug, Default)]
+pub struct CpuStats {
+    #[serde(default)]
+    pub cpu_usage: CpuUsage,
 
-    #[serde(rename = "Binds")]
-    pub binds: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "super::null_as_default")]
+    pub system_cpu_usage: u64,
+
+    #[serde(default, deserialize_with = "super::null_as_default")]
+    pub online_cpus: u32,
 }
 
-#[derive(Serialize, Debug)]
-pub struct ContainerCreationBody {
-    #[serde(rename = "Image")]
-    pub image: String,
+#[derive(Deserialize, Debug, Default)]
+pub struct CpuUsage {
+    #[serde(default)]
+    pub total_usage: u64,
+}
 
-    #[serde(rename = "Cmd")]
-    pub command: Option<String>,
+#[derive(Deserialize, Debug, Default)]
+pub struct MemoryStats {
+    #[serde(default, deserialize_with = "super::null_as_default")]
+    pub usage: u64,
 
-    #[serde(rename = "HostConfig")]
-    pub host_config: HostConfig,
+    #[serde(default, deserialize_with = "super::null_as_default")]
+    pub limit: u64,
+
+    #[serde(default, deserialize_with = "super::null_as_default")]
+    pub stats: HashMap<String, u64>,
+}
+
+impl MemoryStats {
+    /// Usage without the reclaimable page cache, matching what `docker stats` shows.
+    pub fn used(&self) -> u64 {
+        let cache = self
+            .stats
+            .get("inactive_file")
+            .or_else(|| self.stats.get("total_inactive_file"))
+            .copied()
+            .unwrap_or_default();
+        self.usage.saturating_sub(cache)
+    }
 }
 
 #[derive(Deserialize, Debug)]
-pub struct ContainerCreationResponse {
+struct CommitResponse {
     #[serde(rename = "Id")]
-    pub id: String,
+    id: String,
 }
 
-pub async fn create(name: &str, body: ContainerCreationBody) -> Result<String> {
-    let url = Uri::new(
-        DOCKER_SOCK,
-        &format!("/v1.47/containers/create?name={}", name),
-    );
+impl DockerClient {
+    pub async fn list_containers(&self) -> Result<Vec<Container>> {
+        self.get("/containers/json?all=true").await
+    }
+
+    pub async fn start_container(&self, id: &str) -> Result<()> {
+        self.call(Method::POST, &format!("/containers/{}/start", id))
+            .await
+    }
 
-    let req = hyper::Request::builder()
-        .uri(url)
-        .method("POST")
-        .header("Content-Type", "application/json")
-        .body(Full::from(serde_json::to_string(&body)?))?;
+    pub async fn stop_container(&self, id: &str, timeout: Option<u32>) -> Result<()> {
+        self.call(
+            Method::POST,
+            &format!("/containers/{}/stop{}", id, timeout_query(timeout)),
+        )
+        .await
+    }
+
+    pub async fn restart_container(&self, id: &str, timeout: Option<u32>) -> Result<()> {
+        self.call(
+            Method::POST,
+            &format!("/containers/{}/restart{}", id, timeout_query(timeout)),
+        )
+        .await
+    }
+
+    pub async fn pause_container(&self, id: &str) -> Result<()> {
+        self.call(Method::POST, &format!("/containers/{}/pause", id))
+            .await
+    }
+
+    pub async fn unpause_container(&self, id: &str) -> Result<()> {
+        self.call(Method::POST, &format!("/containers/{}/unpause", id))
+            .await
+    }
+
+    /// Sends `signal` to the container, Docker defaults to SIGKILL when it is empty.
+    pub async fn kill_container(&self, id: &str, signal: &str) -> Result<()> {
+        let query = if signal.is_empty() {
+            String::new()
+        } else {
+            format!("?signal={}", utf8_percent_encode(signal, NON_ALPHANUMERIC))
+        };
+        self.call(Method::POST, &format!("/containers/{}/kill{}", id, query))
+            .await
+    }
+
+    pub async fn remove_container(&self, id: &str, options: RemoveOptions) -> Result<()> {
+        self.call(
+            Method::DELETE,
+            &format!(
+                "/containers/{}?force={}&v={}",
+                id, options.force, options.volumes
+            ),
+        )
+        .await
+    }
+
+    /// The last 1000 log lines, each prefixed with its RFC 3339 timestamp.
+    pub async fn container_logs(&self, id: &str) -> Result<Vec<String>> {
+        self.logs(id, "tail=1000").await
+    }
+
+    /// Log lines written at or after `since`, given as Unix time with optional fractional seconds.
+    pub async fn container_logs_since(&self, id: &str, since: &str) -> Result<Vec<String>> {
+        self.logs(id, &format!("since={since}")).await
+    }
+
+    async fn logs(&self, id: &str, query: &str) -> Result<Vec<String>> {
+        let req = self
+            .builder(
+                Method::GET,
+                &format!(
+                    "/containers/{}/logs?stderr=true&stdout=true&timestamps=true&{}",
+                    id, query
+                ),
+            )
+            .body(Full::default())?;
+
+        let body = self.send(req).await?.collect().await?.to_bytes();
+        let reader = super::demultiplex(&body);
+
+        let mut lines = vec![];
+        for l in reader.as_slice().lines() {
+            match l {
+                Ok(line) => lines.push(line),
+                Err(err) => debug!("Skipping log line: {err:?}"),
+            }
+        }
 
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-    let res = client.request(req).await?;
-    if res.status() != 201 {
-        let status = res.status();
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("status: {status}, {error:?}")
+        Ok(lines)
     }
 
-    let body = res.collect().await?.aggregate();
-    let container_creation_response: ContainerCreationResponse =
-        serde_json::from_reader(body.reader())?;
+    pub async fn create_container(
+        &self,
+        name: &str,
+        body: ContainerCreationBody,
+    ) -> Result<String> {
+        let response: ContainerCreationResponse = self
+            .post_json(
+                &format!(
+                    "/containers/create?name={}",
+                    utf8_percent_encode(name, NON_ALPHANUMERIC)
+                ),
+                &body,
+            )
+            .await?;
+
+        Ok(response.id)
+    }
+
+    /// Inspects a container by id or name, returning `None` if it does not exist.
+    pub async fn inspect_container(&self, id: &str) -> Result<Option<ContainerInspection>> {
+        let req = self
+            .builder(Method::GET, &format!("/containers/{}/json", id))
+            .body(Full::default())?;
+
+        let res = self.execute(req).await?;
+        if res.status() == 404 {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            super::client::json(super::client::check(res).await?).await?,
+        ))
+    }
+
+    pub async fn rename_container(&self, id: &str, name: &str) -> Result<()> {
+        self.call(
+            Method::POST,
+            &format!(
+                "/containers/{}/rename?name={}",
+                id,
+                utf8_percent_encode(name, NON_ALPHANUMERIC)
+            ),
+        )
+        .await
+    }
+
+    /// Extracts a tar archive into the container at `path`.
+    pub async fn upload_to_container(&self, id: &str, path: &str, archive: Vec<u8>) -> Result<()> {
+        let req = self
+            .builder(Method::PUT, &archive_path(id, path))
+            .header("Content-Type", "application/x-tar")
+            .body(Full::from(archive))?;
+
+        self.send(req).await?.collect().await?;
+        Ok(())
+    }
+
+    /// Extracts a tar archive into `path` while it is still being received, the upload
+    /// fails when `chunks` yields an error.
+    pub async fn upload_stream_to_container(
+        &self,
+        id: &str,
+        path: &str,
+        chunks: Receiver<Result<Bytes, BoxError>>,
+    ) -> Result<()> {
+        let body = StreamBody::new(ReceiverStream::new(chunks).map(|chunk| chunk.map(Frame::data)));
+        let req = self
+            .builder(Method::PUT, &archive_path(id, path))
+            .header("Content-Type", "application/x-tar")
+            .body(BoxBody::new(body))?;
+
+        client::check(self.execute_streamed(req).await?)
+            .await?
+            .collect()
+            .await?;
+        Ok(())
+    }
+
+    /// Requests a tar archive of `path`, the body is returned unread so it can be streamed.
+    pub async fn download_from_container(&self, id: &str, path: &str) -> Result<Incoming> {
+        let req = self
+            .builder(Method::GET, &archive_path(id, path))
+            .body(Full::default())?;
+
+        Ok(self.send(req).await?.into_body())
+    }
+
+    /// Lists the processes running in the container, `ps_args` defaults to `-ef` when empty.
+    pub async fn top(&self, id: &str, ps_args: &str) -> Result<Top> {
+        let query = if ps_args.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "?ps_args={}",
+                utf8_percent_encode(ps_args, NON_ALPHANUMERIC)
+            )
+        };
+
+        self.get(&format!("/containers/{}/top{}", id, query)).await
+    }
+
+    /// Current resource usage, without waiting for a second sample to compare against.
+    pub async fn container_stats(&self, id: &str) -> Result<Stats> {
+        self.get(&format!(
+            "/containers/{}/stats?stream=false&one-shot=true",
+            id
+        ))
+        .await
+    }
+
+    /// Files added, modified or deleted in the container's writable layer.
+    pub async fn container_changes(&self, id: &str) -> Result<Vec<Change>> {
+        // Docker answers with null instead of an empty list when nothing changed.
+        let changes: Option<Vec<Change>> = self.get(&format!("/containers/{}/changes", id)).await?;
+        Ok(changes.unwrap_or_default())
+    }
+
+    /// Creates an image `repository:tag` from the container and returns its id.
+    pub async fn commit_container(
+        &self,
+        id: &str,
+        repository: &str,
+        tag: &str,
+        comment: &str,
+        pause: bool,
+    ) -> Result<String> {
+        let path = format!(
+            "/commit?container={}&repo={}&tag={}&comment={}&pause={}",
+            id,
+            utf8_percent_encode(repository, NON_ALPHANUMERIC),
+            utf8_percent_encode(tag, NON_ALPHANUMERIC),
+            utf8_percent_encode(comment, NON_ALPHANUMERIC),
+            pause
+        );
+        let commit: CommitResponse = self.post(&path).await?;
+
+        Ok(commit.id)
+    }
+
+    /// Requests a tar export of the container's filesystem, the body is returned unread.
+    pub async fn export_container(&self, id: &str) -> Result<Incoming> {
+        let req = self
+            .builder(Method::GET, &format!("/containers/{}/export", id))
+            .body(Full::default())?;
+
+        Ok(self.send(req).await?.into_body())
+    }
+
+    /// Blocks until the container stops and returns its exit code.
+    pub async fn wait_container(&self, id: &str) -> Result<i64> {
+        let response: WaitResponse = self.post(&format!("/containers/{}/wait", id)).await?;
+
+        Ok(response.status_code)
+    }
+}
+
+fn timeout_query(timeout: Option<u32>) -> String {
+    timeout.map(|t| format!("?t={t}")).unwrap_or_default()
+}
 
-    Ok(container_creation_response.id)
+fn archive_path(id: &str, path: &str) -> String {
+    format!(
+        "/containers/{}/archive?path={}",
+        id,
+        utf8_percent_encode(path, NON_ALPHANUMERIC)
+    )
 }
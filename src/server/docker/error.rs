@@ -0,0 +1,74 @@
+use std::fmt;
+
+use hyper::StatusCode;
+use serde::Deserialize;
+use tonic::{Code, Status};
+
+/// Body the daemon sends along with error statuses.
+#[derive(Deserialize, Debug)]
+pub(super) struct ErrorResponse {
+    pub message: String,
+}
+
+/// A failed Docker API call, classified by what the caller can do about it.
+#[derive(Debug)]
+pub enum Error {
+    NotFound(String),
+    Conflict(String),
+    NotModified,
+    /// The daemon could not be reached or is not ready to serve requests.
+    Unavailable(String),
+    BadRequest(String),
+    Server {
+        status: StatusCode,
+        message: String,
+    },
+    /// The daemon answered with something we could not parse.
+    Decode(String),
+}
+
+impl Error {
+    pub(super) fn from_status(status: StatusCode, message: String) -> Self {
+        match status {
+            StatusCode::NOT_MODIFIED => Self::NotModified,
+            StatusCode::BAD_REQUEST => Self::BadRequest(message),
+            StatusCode::NOT_FOUND => Self::NotFound(message),
+            StatusCode::CONFLICT => Self::Conflict(message),
+            StatusCode::SERVICE_UNAVAILABLE => Self::Unavailable(message),
+            status => Self::Server { status, message },
+        }
+    }
+
+    pub(crate) fn code(&self) -> Code {
+        match self {
+            Self::NotFound(_) => Code::NotFound,
+            Self::Conflict(_) => Code::AlreadyExists,
+            Self::NotModified => Code::FailedPrecondition,
+            Self::Unavailable(_) => Code::Unavailable,
+            Self::BadRequest(_) => Code::InvalidArgument,
+            Self::Server { .. } | Self::Decode(_) => Code::Internal,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(message) => write!(f, "not found: {message}"),
+            Self::Conflict(message) => write!(f, "conflict: {message}"),
+            Self::NotModified => write!(f, "not modified"),
+            Self::Unavailable(message) => write!(f, "docker unavailable: {message}"),
+            Self::BadRequest(message) => write!(f, "bad request: {message}"),
+            Self::Server { status, message } => write!(f, "status: {status}, {message}"),
+            Self::Decode(message) => write!(f, "unexpected docker response: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<&Error> for Status {
+    fn from(err: &Error) -> Self {
+        Status::new(err.code(), err.to_string())
+    }
+}
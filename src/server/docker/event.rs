@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::Method;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::proto;
+
+use super::client::DockerClient;
+
+const EVENT_FILTERS: &str = r#"{"type":["container","image","network","volume"]}"#;
+
+#[derive(Deserialize, Debug)]
+pub struct Event {
+    #[serde(rename = "Type", default, deserialize_with = "super::null_as_default")]
+    pub event_type: String,
+
+    #[serde(
+        rename = "Action",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    pub action: String,
+
+    /// Older Podman releases only report the action here.
+    #[serde(
+        rename = "status",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    pub status: String,
+
+    #[serde(rename = "Actor", default)]
+    pub actor: Actor,
+
+    #[serde(default)]
+    pub time: i64,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Actor {
+    #[serde(rename = "ID", default, deserialize_with = "super::null_as_default")]
+    pub id: String,
+
+    #[serde(
+        rename = "Attributes",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    pub attributes: HashMap<String, String>,
+}
+
+impl From<Event> for proto::DockerEvent {
+    fn from(e: Event) -> Self {
+        Self {
+            event_type: e.event_type,
+            action: if e.action.is_empty() {
+                e.status
+            } else {
+                e.action
+            },
+            id: e.actor.id,
+            attributes: e.actor.attributes,
+            time: e.time,
+        }
+    }
+}
+
+/// Reads the newline separated JSON events of a `/events` response.
+pub struct EventReader {
+    body: Incoming,
+    buffer: Vec<u8>,
+}
+
+impl EventReader {
+    pub async fn next(&mut self) -> Result<Option<Event>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+
+                match serde_json::from_slice(&line) {
+                    Ok(event) => return Ok(Some(event)),
+                    Err(err) => {
+                        debug!("Skipping unparsable event: {err:?}");
+                        continue;
+                    }
+                }
+            }
+
+            match self.body.frame().await {
+                Some(frame) => {
+                    if let Ok(data) = frame?.into_data() {
+                        self.buffer.extend_from_slice(&data);
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl DockerClient {
+    /// Subscribes to container, image, network and volume lifecycle events.
+    pub async fn events(&self) -> Result<EventReader> {
+        let req = self
+            .builder(
+                Method::GET,
+                &format!(
+                    "/events?filters={}",
+                    utf8_percent_encode(EVENT_FILTERS, NON_ALPHANUMERIC)
+                ),
+            )
+            .body(Full::default())?;
+
+        Ok(EventReader {
+            body: self.send(req).await?.into_body(),
+            buffer: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::BufRead;
+
+use anyhow::bail;
+use anyhow::Result;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use hyper::upgrade::Upgraded;
+use hyper::Method;
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+
+use super::client::{self, DockerClient};
+
+#[derive(Serialize, Debug)]
+struct ExecCreationBody<'a> {
+    #[serde(rename = "AttachStdin")]
+    attach_stdin: bool,
+
+    #[serde(rename = "AttachStdout")]
+    attach_stdout: bool,
+
+    #[serde(rename = "AttachStderr")]
+    attach_stderr: bool,
+
+    #[serde(rename = "Tty")]
+    tty: bool,
+
+    #[serde(rename = "Cmd")]
+    command: &'a [String],
+}
+
+#[derive(Deserialize, Debug)]
+struct ExecCreationResponse {
+    #[serde(rename = "Id")]
+    id: String,
+}
+
+#[derive(Serialize, Debug)]
+struct ExecStartBody {
+    #[serde(rename = "Detach")]
+    detach: bool,
+
+    #[serde(rename = "Tty")]
+    tty: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExecInspection {
+    #[serde(rename = "ExitCode")]
+    exit_code: Option<i64>,
+}
+
+/// Output and exit code of a finished exec.
+pub struct ExecResult {
+    pub exit_code: i64,
+    pub output: Vec<String>,
+}
+
+/// A running exec with a TTY, `io` carries stdin and the combined output.
+pub struct InteractiveExec {
+    pub id: String,
+    pub io: TokioIo<Upgraded>,
+}
+
+impl DockerClient {
+    /// Runs `command` inside the container and waits for it to finish.
+    pub async fn run_exec(&self, container_id: &str, command: &[String]) -> Result<ExecResult> {
+        let id = self.create_exec(container_id, command, false).await?;
+        let output = self.start_exec(&id).await?;
+        let exit_code = self.exec_exit_code(&id).await?;
+
+        Ok(ExecResult { exit_code, output })
+    }
+
+    /// Starts `command` in the container with a TTY and hijacks the connection for raw IO.
+    pub async fn start_interactive_exec(
+        &self,
+        container_id: &str,
+        command: &[String],
+    ) -> Result<InteractiveExec> {
+        let id = self.create_exec(container_id, command, true).await?;
+
+        let body = ExecStartBody {
+            detach: false,
+            tty: true,
+        };
+        let req = self
+            .builder(Method::POST, &format!("/exec/{}/start", id))
+            .header("Content-Type", "application/json")
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "tcp")
+            .body(Full::from(serde_json::to_string(&body)?))?;
+
+        let res = self.execute(req).await?;
+        if res.status() != 101 && res.status() != 200 {
+            client::check(res).await?;
+            bail!("exec {id} was not upgraded");
+        }
+
+        let io = TokioIo::new(hyper::upgrade::on(res).await?);
+        Ok(InteractiveExec { id, io })
+    }
+
+    pub async fn resize_exec(&self, id: &str, rows: u32, columns: u32) -> Result<()> {
+        self.call(
+            Method::POST,
+            &format!("/exec/{}/resize?h={}&w={}", id, rows, columns),
+        )
+        .await
+    }
+
+    pub async fn exec_exit_code(&self, id: &str) -> Result<i64> {
+        let inspection: ExecInspection = self.get(&format!("/exec/{}/json", id)).await?;
+        Ok(inspection.exit_code.unwrap_or(-1))
+    }
+
+    async fn create_exec(
+        &self,
+        container_id: &str,
+        command: &[String],
+        interactive: bool,
+    ) -> Result<String> {
+        let body = ExecCreationBody {
+            attach_stdin: interactive,
+            attach_stdout: true,
+            attach_stderr: true,
+            tty: interactive,
+            command,
+        };
+
+        let response: ExecCreationResponse = self
+            .post_json(&format!("/containers/{}/exec", container_id), &body)
+            .await?;
+        Ok(response.id)
+    }
+
+    async fn start_exec(&self, id: &str) -> Result<Vec<String>> {
+        let body = ExecStartBody {
+            detach: false,
+            tty: false,
+        };
+        let req = self
+            .builder(Method::POST, &format!("/exec/{}/start", id))
+            .header("Content-Type", "application/json")
+            .body(Full::from(serde_json::to_string(&body)?))?;
+
+        let body = self.send(req).await?.collect().await?.to_bytes();
+        Ok(super::demultiplex(&body)
+            .as_slice()
+            .lines()
+            .map_while(Result::ok)
+            .collect())
+    }
+}
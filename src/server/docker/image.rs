@@ -1,42 +1,113 @@
-use std::io::Read;
+use std::path::Path;
 
 use anyhow::bail;
 use anyhow::Result;
 use http_body_util::BodyExt;
 use http_body_util::Full;
-use hyper::body::Bytes;
-use hyper_util::client::legacy::Client;
-use hyperlocal::UnixClientExt;
-use hyperlocal::{UnixConnector, Uri};
-use prost::bytes::Buf;
-
-use crate::docker::Error;
-
-use super::DOCKER_SOCK;
-
-pub async fn pull(name: &str, tag: &str) -> Result<()> {
-    let url = Uri::new(
-        DOCKER_SOCK,
-        &format!("/v1.47/images/create?fromImage={name}&tag={tag}"),
-    );
-
-    let req = hyper::Request::builder()
-        .uri(url)
-        .method("POST")
-        .body(Full::from(""))?;
-
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
-    let res = client.request(req).await?;
-    if res.status() != 200 {
-        let status = res.status();
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("status: {status}, {error:?}")
+use hyper::Method;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use tokio::sync::mpsc::Sender;
+use tracing::debug;
+
+use super::client::DockerClient;
+
+#[derive(Deserialize, Debug)]
+struct BuildMessage {
+    stream: Option<String>,
+    error: Option<String>,
+}
+
+impl DockerClient {
+    pub async fn pull_image(&self, name: &str, tag: &str) -> Result<()> {
+        let req = self
+            .builder(
+                Method::POST,
+                &format!(
+                    "/images/create?fromImage={}&tag={}",
+                    utf8_percent_encode(name, NON_ALPHANUMERIC),
+                    utf8_percent_encode(tag, NON_ALPHANUMERIC)
+                ),
+            )
+            .body(Full::default())?;
+
+        // The pull only finishes once its progress stream has been read to the end.
+        self.send(req).await?.collect().await?;
+        Ok(())
     }
 
-    let body = res.collect().await?.aggregate();
-    let mut dest = String::new();
-    body.reader().read_to_string(&mut dest)?;
+    /// Packs `context` into a tar archive and builds it as image `tag`.
+    /// Build output is forwarded line by line to `output`.
+    pub async fn build_image(
+        &self,
+        context: &Path,
+        dockerfile: &str,
+        tag: &str,
+        output: &Sender<String>,
+    ) -> Result<()> {
+        let mut archive = tar::Builder::new(Vec::new());
+        archive.append_dir_all(".", context)?;
+        let archive = archive.into_inner()?;
+
+        let req = self
+            .builder(
+                Method::POST,
+                &format!(
+                    "/build?t={}&dockerfile={}&rm=true",
+                    utf8_percent_encode(tag, NON_ALPHANUMERIC),
+                    utf8_percent_encode(dockerfile, NON_ALPHANUMERIC)
+                ),
+            )
+            .header("Content-Type", "application/x-tar")
+            .body(Full::from(archive))?;
+
+        let mut body = self.send(req).await?.into_body();
+        let mut buf = Vec::new();
+        while let Some(frame) = body.frame().await {
+            if let Some(chunk) = frame?.data_ref() {
+                buf.extend_from_slice(chunk);
+            }
+
+            for message in drain_build_messages(&mut buf)? {
+                if let Some(error) = message.error {
+                    bail!("image build failed: {error}");
+                }
+
+                if let Some(stream) = message.stream {
+                    for line in stream.lines().filter(|l| !l.trim().is_empty()) {
+                        if let Err(err) = output.send(line.to_string()).await {
+                            debug!("Dropping build output: {err:?}");
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses all complete JSON messages in `buf`, leaving a trailing partial message in place.
+fn drain_build_messages(buf: &mut Vec<u8>) -> Result<Vec<BuildMessage>> {
+    let mut messages = Vec::new();
+    let mut stream = serde_json::Deserializer::from_slice(buf).into_iter::<BuildMessage>();
+    let mut consumed = 0;
+
+    loop {
+        match stream.next() {
+            Some(Ok(message)) => {
+                consumed = stream.byte_offset();
+                messages.push(message);
+            }
+            Some(Err(err)) if err.is_eof() => break,
+            Some(Err(err)) => return Err(err.into()),
+            None => {
+                consumed = stream.byte_offset();
+                break;
+            }
+        }
+    }
 
-    Ok(())
+    buf.drain(..consumed);
+    Ok(messages)
 }
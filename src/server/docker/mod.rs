@@ -1,13 +1,45 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
+pub mod client;
 pub mod container;
+pub mod error;
+pub mod event;
+pub mod exec;
 pub mod image;
+pub mod network;
+mod tls;
 pub mod version;
+pub mod volume;
 
-const DOCKER_SOCK: &str = "/var/run/docker.sock";
+pub use client::{DockerClient, Endpoint};
 
-#[derive(Deserialize, Debug)]
-struct Error {
-    #[allow(dead_code)]
-    message: String,
+/// Podman sends `null` in places where Docker sends empty strings or lists.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Strips the 8 byte frame headers Docker puts in front of stdout/stderr chunks
+/// of non-TTY streams. Data that is not framed is returned unchanged.
+fn demultiplex(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len());
+    let mut rest = data;
+
+    while !rest.is_empty() {
+        let framed = rest.len() >= 8 && rest[0] <= 2 && rest[1..4] == [0, 0, 0];
+        if !framed {
+            output.extend_from_slice(rest);
+            break;
+        }
+
+        let size = u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let end = (8 + size).min(rest.len());
+        output.extend_from_slice(&rest[8..end]);
+        rest = &rest[end..];
+    }
+
+    output
 }
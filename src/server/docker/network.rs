@@ -0,0 +1,82 @@
+use anyhow::Result;
+use http_body_util::{BodyExt, Full};
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+
+use super::client::{self, DockerClient};
+use super::container::EndpointSettings;
+
+#[derive(Serialize, Debug)]
+struct NetworkCreationBody<'a> {
+    #[serde(rename = "Name")]
+    name: &'a str,
+}
+
+#[derive(Deserialize, Debug)]
+struct NetworkCreationResponse {}
+
+#[derive(Serialize, Debug)]
+struct NetworkConnectBody<'a> {
+    #[serde(rename = "Container")]
+    container: &'a str,
+    #[serde(rename = "EndpointConfig")]
+    endpoint_config: &'a EndpointSettings,
+}
+
+impl DockerClient {
+    pub async fn network_exists(&self, name: &str) -> Result<bool> {
+        let req = self
+            .builder(Method::GET, &format!("/networks/{}", name))
+            .body(Full::default())?;
+
+        let res = self.execute(req).await?;
+        if res.status() == 404 {
+            return Ok(false);
+        }
+
+        client::check(res).await?;
+        Ok(true)
+    }
+
+    pub async fn create_network(&self, name: &str) -> Result<()> {
+        let _: NetworkCreationResponse = self
+            .post_json("/networks/create", &NetworkCreationBody { name })
+            .await?;
+        Ok(())
+    }
+
+    /// Attaches an existing container to another network.
+    pub async fn connect_network(
+        &self,
+        name: &str,
+        container: &str,
+        endpoint_config: &EndpointSettings,
+    ) -> Result<()> {
+        let body = NetworkConnectBody {
+            container,
+            endpoint_config,
+        };
+        let req = self
+            .builder(Method::POST, &format!("/networks/{}/connect", name))
+            .header("Content-Type", "application/json")
+            .body(Full::from(serde_json::to_string(&body)?))?;
+
+        self.send(req).await?.collect().await?;
+        Ok(())
+    }
+
+    /// Removes a network, a network that is already gone is not an error.
+    pub async fn remove_network(&self, name: &str) -> Result<()> {
+        let req = self
+            .builder(Method::DELETE, &format!("/networks/{}", name))
+            .body(Full::default())?;
+
+        let res = self.execute(req).await?;
+        if res.status() == 404 {
+            return Ok(());
+        }
+
+        client::check(res).await?;
+        Ok(())
+    }
+}
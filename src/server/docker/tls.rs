@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
+};
+
+use crate::config::DockerTls;
+
+/// Verifies the daemon against the configured CA and authenticates with the client certificate.
+pub(super) fn connector(config: &DockerTls) -> Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(&config.ca_path)
+        .with_context(|| format!("unable to read CA {:?}", config.ca_path))?
+    {
+        roots.add(cert?)?;
+    }
+
+    let certs = CertificateDer::pem_file_iter(&config.cert_path)
+        .with_context(|| format!("unable to read certificate {:?}", config.cert_path))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = PrivateKeyDer::from_pem_file(&config.key_path)
+        .with_context(|| format!("unable to read key {:?}", config.key_path))?;
+
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_client_auth_cert(certs, key)?;
+
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+/// Host part of `host:port`, which the daemon certificate has to be issued for.
+pub(super) fn server_name(address: &str) -> Result<ServerName<'static>> {
+    let host = address
+        .rsplit_once(':')
+        .map_or(address, |(host, _)| host)
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+
+    ServerName::try_from(host.to_owned())
+        .with_context(|| format!("invalid docker host name {host:?}"))
+}
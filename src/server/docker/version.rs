@@ -1,18 +1,11 @@
-use anyhow::bail;
-use anyhow::Result;
-use http_body_util::BodyExt;
+use anyhow::{bail, Context, Result};
 use http_body_util::Full;
-use hyper::body::Bytes;
-use hyper_util::client::legacy::Client;
-use hyperlocal::UnixClientExt;
-use hyperlocal::{UnixConnector, Uri};
-use prost::bytes::Buf;
+use hyper::Method;
 use serde::Deserialize;
 
-use crate::docker::Error;
 use crate::proto;
 
-use super::DOCKER_SOCK;
+use super::client::{self, DockerClient, MAX_API_VERSION};
 
 #[derive(Deserialize, Debug)]
 pub struct Version {
@@ -21,6 +14,35 @@ pub struct Version {
 
     #[serde(rename = "ApiVersion")]
     api_version: String,
+
+    #[serde(rename = "MinAPIVersion")]
+    min_api_version: Option<String>,
+
+    #[serde(
+        rename = "Components",
+        default,
+        deserialize_with = "super::null_as_default"
+    )]
+    components: Vec<Component>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Component {
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+impl Version {
+    /// Podman lists itself as "Podman Engine" among the version components.
+    pub fn engine(&self) -> proto::EngineKind {
+        if self.components.iter().any(|c| c.name.starts_with("Podman")) {
+            proto::EngineKind::Podman
+        } else if self.components.iter().any(|c| c.name == "Engine") {
+            proto::EngineKind::Docker
+        } else {
+            proto::EngineKind::Unknown
+        }
+    }
 }
 
 impl From<Version> for proto::Version {
@@ -28,21 +50,56 @@ impl From<Version> for proto::Version {
         Self {
             version: v.version,
             api_version: v.api_version,
+            negotiated_api_version: String::new(),
         }
     }
 }
 
-pub async fn version() -> Result<Version> {
-    let url = Uri::new(DOCKER_SOCK, "/v1.47/version").into();
-    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
+impl DockerClient {
+    pub async fn version(&self) -> Result<Version> {
+        self.get("/version").await
+    }
+
+    /// Asks the daemon which API versions it supports and uses the newest one
+    /// both sides understand for all further requests.
+    pub async fn negotiate_api_version(&mut self) -> Result<()> {
+        let req = self
+            .unversioned(Method::GET, "/version")
+            .body(Full::default())?;
+        let version: Version = client::json(self.send(req).await?).await?;
 
-    let res = client.get(url).await?;
-    if res.status() != 200 {
-        let body = res.collect().await?.aggregate();
-        let error: Error = serde_json::from_reader(body.reader())?;
-        bail!("{error:?}")
+        let api_version = negotiate(&version.api_version, version.min_api_version.as_deref())?;
+        self.set_api_version(api_version);
+        Ok(())
     }
+}
+
+/// Picks the daemon's version capped at [`MAX_API_VERSION`].
+fn negotiate(daemon: &str, daemon_min: Option<&str>) -> Result<String> {
+    let supported = parse(MAX_API_VERSION)?;
+    if let Some(min) = daemon_min {
+        if parse(min)? > supported {
+            bail!("docker requires api version {min} or newer, {MAX_API_VERSION} is supported");
+        }
+    }
+
+    if parse(daemon)? >= supported {
+        Ok(MAX_API_VERSION.to_owned())
+    } else {
+        Ok(daemon.to_owned())
+    }
+}
 
-    let body = res.collect().await?.aggregate();
-    Ok(serde_json::from_reader(body.reader())?)
+fn parse(version: &str) -> Result<(u32, u32)> {
+    let (major, minor) = version
+        .split_once('.')
+        .with_context(|| format!("invalid api version {version:?}"))?;
+    Ok((
+        major
+            .parse()
+            .with_context(|| format!("invalid api version {version:?}"))?,
+        minor
+            .parse()
+            .with_context(|| format!("invalid api version {version:?}"))?,
+    ))
 }
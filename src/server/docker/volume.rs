@@ -0,0 +1,23 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::client::DockerClient;
+
+#[derive(Serialize, Debug)]
+struct VolumeCreationBody<'a> {
+    #[serde(rename = "Name")]
+    name: &'a str,
+}
+
+#[derive(Deserialize, Debug)]
+struct VolumeCreationResponse {}
+
+impl DockerClient {
+    /// Creates a named volume. Docker returns the existing volume if the name is taken.
+    pub async fn create_volume(&self, name: &str) -> Result<()> {
+        let _: VolumeCreationResponse = self
+            .post_json("/volumes/create", &VolumeCreationBody { name })
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+use tonic::{Code, Status};
+
+use crate::docker;
+
+/// A request the server's own stores cannot serve, classified like Docker errors so
+/// the services answer with a matching gRPC code.
+#[derive(Debug)]
+pub enum Error {
+    NotFound(String),
+    InvalidArgument(String),
+    FailedPrecondition(String),
+}
+
+impl Error {
+    fn code(&self) -> Code {
+        match self {
+            Self::NotFound(_) => Code::NotFound,
+            Self::InvalidArgument(_) => Code::InvalidArgument,
+            Self::FailedPrecondition(_) => Code::FailedPrecondition,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(message)
+            | Self::InvalidArgument(message)
+            | Self::FailedPrecondition(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Maps a typed error anywhere in the chain to its gRPC code, anything else stays
+/// `Unknown`. The message keeps the whole chain so callers see the cause.
+pub fn to_status(err: anyhow::Error) -> Status {
+    let code = if let Some(err) = err.downcast_ref::<docker::error::Error>() {
+        err.code()
+    } else if let Some(err) = err.downcast_ref::<Error>() {
+        err.code()
+    } else {
+        Code::Unknown
+    };
+
+    Status::new(code, format!("{err:#}"))
+}
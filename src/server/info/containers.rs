@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset};
+use futures::StreamExt;
+use tonic::Status;
+use tracing::debug;
+
+use crate::docker::{container::Container, error::Error, DockerClient};
+use crate::error;
+use crate::proto::{self, ContainerStats, DockerInfo, Version};
+
+const MAX_PARALLEL_FETCHES: usize = 8;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_LOG_LINES: usize = 1000;
+
+/// Container data from the previous tick, so unchanged containers are not fetched again.
+#[derive(Default)]
+pub struct ContainerCache {
+    containers: HashMap<String, CachedContainer>,
+}
+
+struct CachedContainer {
+    state: String,
+    logs: Vec<String>,
+    /// Cumulative container and system CPU time of the last stats sample.
+    cpu_time: Option<(u64, u64)>,
+}
+
+impl ContainerCache {
+    /// Lists the containers and fetches their logs and stats concurrently. Containers that
+    /// kept their state reuse the cached logs, running ones only fetch lines written since.
+    pub async fn docker_info(&mut self, docker: &DockerClient) -> Result<DockerInfo, Status> {
+        let version = with_timeout(docker.version()).await?;
+        let engine = version.engine();
+        let mut version: Version = version.into();
+        version.negotiated_api_version = docker.api_version().to_owned();
+
+        let containers = with_timeout(docker.list_containers()).await?;
+
+        let fetches = containers.into_iter().map(|c| {
+            let cached = self.containers.remove(&c.id);
+            async move {
+                let cpu_time = cached.as_ref().and_then(|cached| cached.cpu_time);
+                let cached = cached.filter(|cached| cached.state == c.state);
+                let (logs, stats) = tokio::join!(
+                    fetch_logs(docker, &c, cached),
+                    fetch_stats(docker, &c, cpu_time)
+                );
+                (c, logs, stats)
+            }
+        });
+        let fetched: Vec<_> = futures::stream::iter(fetches)
+            .buffered(MAX_PARALLEL_FETCHES)
+            .collect()
+            .await;
+
+        let mut container_list = Vec::with_capacity(fetched.len());
+        for (c, logs, stats) in fetched {
+            let (stats, cpu_time) = stats.unzip();
+            self.containers.insert(
+                c.id.clone(),
+                CachedContainer {
+                    state: c.state.clone(),
+                    logs: logs.clone(),
+                    cpu_time,
+                },
+            );
+            container_list.push(proto::Container {
+                id: c.id,
+                names: c.names,
+                image: c.image,
+                command: c.command,
+                created: c.created,
+                ports: c.ports.iter().map(proto::Port::from).collect(),
+                status: c.status,
+                state: c.state,
+                logs,
+                stats,
+            });
+        }
+
+        Ok(DockerInfo {
+            version: Some(version),
+            container_list,
+            engine: engine.into(),
+        })
+    }
+}
+
+/// A daemon that accepts connections but never answers must not stall the updater.
+async fn with_timeout<T>(call: impl Future<Output = anyhow::Result<T>>) -> Result<T, Status> {
+    match tokio::time::timeout(FETCH_TIMEOUT, call).await {
+        Ok(result) => result.map_err(error::to_status),
+        Err(_) => Err((&Error::Unavailable("request timed out".to_owned())).into()),
+    }
+}
+
+/// Fetches what is missing from `cached`, falling back to it if Docker fails or is slow.
+async fn fetch_logs(
+    docker: &DockerClient,
+    container: &Container,
+    cached: Option<CachedContainer>,
+) -> Vec<String> {
+    let since = match &cached {
+        Some(cached) if container.state != "running" => return cached.logs.clone(),
+        Some(cached) => cached.logs.last().and_then(|line| timestamp(line)),
+        None => None,
+    };
+
+    let result = match since {
+        Some(since) => {
+            let query = format!(
+                "{}.{:09}",
+                since.timestamp(),
+                since.timestamp_subsec_nanos()
+            );
+            tokio::time::timeout(
+                FETCH_TIMEOUT,
+                docker.container_logs_since(&container.id, &query),
+            )
+            .await
+        }
+        None => tokio::time::timeout(FETCH_TIMEOUT, docker.container_logs(&container.id)).await,
+    };
+
+    let mut logs = cached.map(|cached| cached.logs).unwrap_or_default();
+    match result {
+        Ok(Ok(lines)) if since.is_some() => {
+            // `since` is inclusive, lines already known come back again.
+            logs.extend(
+                lines
+                    .into_iter()
+                    .filter(|line| timestamp(line).is_none_or(|t| Some(t) > since)),
+            );
+            let excess = logs.len().saturating_sub(MAX_LOG_LINES);
+            logs.drain(..excess);
+            logs
+        }
+        Ok(Ok(lines)) => lines,
+        Ok(Err(err)) => {
+            debug!("Keeping cached logs of {}: {err:?}", container.id);
+            logs
+        }
+        Err(_) => {
+            debug!("Keeping cached logs of {}: timed out", container.id);
+            logs
+        }
+    }
+}
+
+/// Samples a running container, CPU usage is measured against the previous sample.
+async fn fetch_stats(
+    docker: &DockerClient,
+    container: &Container,
+    cpu_time: Option<(u64, u64)>,
+) -> Option<(ContainerStats, (u64, u64))> {
+    if container.state != "running" {
+        return None;
+    }
+
+    let stats =
+        match tokio::time::timeout(FETCH_TIMEOUT, docker.container_stats(&container.id)).await {
+            Ok(Ok(stats)) => stats,
+            Ok(Err(err)) => {
+                debug!("No stats for {}: {err:?}", container.id);
+                return None;
+            }
+            Err(_) => {
+                debug!("No stats for {}: timed out", container.id);
+                return None;
+            }
+        };
+
+    let cpu = &stats.cpu_stats;
+    let current = (cpu.cpu_usage.total_usage, cpu.system_cpu_usage);
+    let cpu_usage = match cpu_time {
+        Some((total, system)) if current.1 > system => {
+            let container_delta = current.0.saturating_sub(total) as f64;
+            let system_delta = (current.1 - system) as f64;
+            (container_delta / system_delta * cpu.online_cpus.max(1) as f64 * 100.0) as f32
+        }
+        _ => 0.0,
+    };
+
+    Some((
+        ContainerStats {
+            cpu_usage,
+            memory_usage: stats.memory_stats.used(),
+            memory_limit: stats.memory_stats.limit,
+        },
+        current,
+    ))
+}
+
+fn timestamp(line: &str) -> Option<DateTime<FixedOffset>> {
+    let (timestamp, _) = line.split_once(' ')?;
+    DateTime::parse_from_rfc3339(timestamp).ok()
+}
@@ -0,0 +1,235 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use prost::Message;
+
+use crate::proto::{ContainerSample, HistoryReply, HistoryRequest, HistorySample, InfoReply};
+
+/// New samples written to disk at once, so a crash loses at most this many.
+const SAVE_EVERY: usize = 60;
+
+/// The most recent samples, oldest first. With a path it survives restarts,
+/// persisted as an encoded `HistoryReply`.
+pub struct History {
+    samples: VecDeque<HistorySample>,
+    capacity: usize,
+    path: Option<PathBuf>,
+    unsaved: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            path: None,
+            unsaved: 0,
+        }
+    }
+
+    pub fn open(capacity: usize, path: PathBuf) -> Result<Self> {
+        let mut history = Self::new(capacity);
+        if path.exists() {
+            let reply = HistoryReply::decode(std::fs::read(&path)?.as_slice())
+                .with_context(|| format!("invalid history file {path:?}"))?;
+            for sample in reply.samples {
+                history.push(sample);
+            }
+        }
+        history.set_path(path);
+        history.unsaved = 0;
+
+        Ok(history)
+    }
+
+    /// Persists to `path` from now on, replacing whatever is there on the next save.
+    pub fn set_path(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    /// Appends a sample, dropping the oldest one when full.
+    pub fn push(&mut self, sample: HistorySample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        self.unsaved += 1;
+    }
+
+    /// Like [`History::push`], handing out a snapshot to save every [`SAVE_EVERY`] samples
+    /// when persisted.
+    pub fn record(&mut self, sample: HistorySample) -> Option<Snapshot> {
+        self.push(sample);
+        if self.unsaved < SAVE_EVERY {
+            return None;
+        }
+
+        self.snapshot()
+    }
+
+    /// Copies the samples so they can be written without holding on to the history.
+    /// They count as saved from now on.
+    pub fn snapshot(&mut self) -> Option<Snapshot> {
+        let path = self.path.clone()?;
+        self.unsaved = 0;
+
+        Some(Snapshot {
+            path,
+            reply: HistoryReply {
+                samples: self.samples.iter().cloned().collect(),
+            },
+        })
+    }
+
+    /// Samples within the requested range, averaged in consecutive groups when there
+    /// are more than `max_points`.
+    pub fn query(&self, request: &HistoryRequest) -> Vec<HistorySample> {
+        let samples: Vec<&HistorySample> = self
+            .samples
+            .iter()
+            .filter(|s| s.timestamp >= request.from)
+            .filter(|s| request.to == 0 || s.timestamp <= request.to)
+            .collect();
+
+        let max_points = request.max_points as usize;
+        if max_points == 0 || samples.len() <= max_points {
+            return samples.into_iter().cloned().collect();
+        }
+
+        samples
+            .chunks(samples.len().div_ceil(max_points))
+            .map(average)
+            .collect()
+    }
+}
+
+/// Samples taken by [`History::snapshot`], waiting to be written.
+pub struct Snapshot {
+    path: PathBuf,
+    reply: HistoryReply,
+}
+
+impl Snapshot {
+    /// Encodes and writes the samples on the blocking pool, replacing the file atomically.
+    pub async fn save(self) -> Result<()> {
+        tokio::task::spawn_blocking(move || {
+            let tmp_path = self.path.with_extension("tmp");
+            std::fs::write(&tmp_path, self.reply.encode_to_vec())?;
+            std::fs::rename(tmp_path, &self.path)?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+/// Condenses a reply into a sample taken at `timestamp`, in unix milliseconds.
+pub fn sample(reply: &InfoReply, timestamp: i64) -> HistorySample {
+    let memory = reply.memory_info.unwrap_or_default();
+    let disks = reply
+        .disk_info
+        .as_ref()
+        .map(|d| d.disks.as_slice())
+        .unwrap_or_default();
+    let networks = reply
+        .network_info
+        .as_ref()
+        .map(|n| n.networks.as_slice())
+        .unwrap_or_default();
+
+    HistorySample {
+        timestamp,
+        cpu_usage: reply
+            .cpu_info
+            .as_ref()
+            .map(|c| c.cpus.iter().map(|cpu| cpu.cpu_usage).collect())
+            .unwrap_or_default(),
+        memory_used: memory.used,
+        memory_total: memory.total,
+        swap_used: memory.swap_used,
+        swap_total: memory.swap_total,
+        disk_used: disks
+            .iter()
+            .map(|d| d.total_space.saturating_sub(d.available_space))
+            .sum(),
+        disk_total: disks.iter().map(|d| d.total_space).sum(),
+        network_received: networks.iter().map(|n| n.received).sum(),
+        network_transmitted: networks.iter().map(|n| n.transmitted).sum(),
+        containers: reply
+            .docker_info
+            .as_ref()
+            .map(|d| {
+                d.container_list
+                    .iter()
+                    .filter_map(|c| {
+                        let stats = c.stats?;
+                        Some(ContainerSample {
+                            id: c.id.clone(),
+                            name: c.names.first().cloned().unwrap_or_default(),
+                            cpu_usage: stats.cpu_usage,
+                            memory_usage: stats.memory_usage,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// Averages a group of samples into one stamped with the time of the last.
+fn average(samples: &[&HistorySample]) -> HistorySample {
+    let count = samples.len() as u64;
+    let mean =
+        |value: fn(&HistorySample) -> u64| samples.iter().map(|s| value(s)).sum::<u64>() / count;
+
+    let cores = samples.iter().map(|s| s.cpu_usage.len()).max().unwrap_or(0);
+    let cpu_usage = (0..cores)
+        .map(|core| {
+            let usages: Vec<f32> = samples
+                .iter()
+                .filter_map(|s| s.cpu_usage.get(core).copied())
+                .collect();
+            usages.iter().sum::<f32>() / usages.len() as f32
+        })
+        .collect();
+
+    // Containers only seen in part of the group are averaged over the samples they appear in.
+    let mut containers: Vec<(ContainerSample, u64)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for container in samples.iter().flat_map(|s| &s.containers) {
+        match positions.get(container.id.as_str()) {
+            Some(&i) => {
+                let (sum, seen) = &mut containers[i];
+                sum.name.clone_from(&container.name);
+                sum.cpu_usage += container.cpu_usage;
+                sum.memory_usage += container.memory_usage;
+                *seen += 1;
+            }
+            None => {
+                positions.insert(&container.id, containers.len());
+                containers.push((container.clone(), 1));
+            }
+        }
+    }
+
+    HistorySample {
+        timestamp: samples.last().map(|s| s.timestamp).unwrap_or_default(),
+        cpu_usage,
+        memory_used: mean(|s| s.memory_used),
+        memory_total: mean(|s| s.memory_total),
+        swap_used: mean(|s| s.swap_used),
+        swap_total: mean(|s| s.swap_total),
+        disk_used: mean(|s| s.disk_used),
+        disk_total: mean(|s| s.disk_total),
+        network_received: mean(|s| s.network_received),
+        network_transmitted: mean(|s| s.network_transmitted),
+        containers: containers
+            .into_iter()
+            .map(|(mut sum, seen)| {
+                sum.cpu_usage /= seen as f32;
+                sum.memory_usage /= seen;
+                sum
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Instant;
+
+use sysinfo::{CpuRefreshKind, Disks, MemoryRefreshKind, Networks, RefreshKind, System};
+
+use crate::proto::{Cpu, CpuInfo, Disk, DiskInfo, MemoryInfo, Network, NetworkInfo};
+
+/// Host metrics taken at one point in time.
+#[derive(Clone, Debug, Default)]
+pub struct HostMetrics {
+    pub name: String,
+    pub kernel_version: String,
+    pub os_version: String,
+    pub host_name: String,
+    pub memory_info: MemoryInfo,
+    pub disk_info: DiskInfo,
+    pub cpu_info: CpuInfo,
+    pub network_info: NetworkInfo,
+}
+
+/// A source of host metrics. Sampling may block briefly, so callers run it off
+/// the async workers.
+pub trait MetricsProvider: Send + 'static {
+    fn sample(&mut self) -> HostMetrics;
+}
+
+/// Reads metrics through sysinfo, keeping one `System` around so CPU usage is
+/// measured against the previous sample instead of sleeping for a fresh one.
+/// Network throughput works the same way.
+pub struct SysinfoMetrics {
+    system: System,
+    disks: Disks,
+    networks: Networks,
+    refreshed_at: Instant,
+}
+
+impl SysinfoMetrics {
+    pub fn new() -> Self {
+        Self {
+            system: System::new_with_specifics(
+                RefreshKind::nothing()
+                    .with_cpu(CpuRefreshKind::everything())
+                    .with_memory(MemoryRefreshKind::everything()),
+            ),
+            disks: Disks::new_with_refreshed_list(),
+            networks: Networks::new_with_refreshed_list(),
+            refreshed_at: Instant::now(),
+        }
+    }
+}
+
+impl Default for SysinfoMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsProvider for SysinfoMetrics {
+    fn sample(&mut self) -> HostMetrics {
+        self.system.refresh_cpu_all();
+        self.system.refresh_memory();
+        self.disks.refresh(true);
+        self.networks.refresh(true);
+        let elapsed = self.refreshed_at.elapsed().as_secs_f64();
+        self.refreshed_at = Instant::now();
+
+        HostMetrics {
+            name: System::name().unwrap_or_default(),
+            kernel_version: System::kernel_version().unwrap_or_default(),
+            os_version: System::os_version().unwrap_or_default(),
+            host_name: System::host_name().unwrap_or_default(),
+            memory_info: memory_info(&self.system),
+            disk_info: disk_info(&self.disks),
+            cpu_info: cpu_info(&self.system),
+            network_info: network_info(&self.networks, elapsed),
+        }
+    }
+}
+
+fn memory_info(sys: &System) -> MemoryInfo {
+    MemoryInfo {
+        total: sys.total_memory(),
+        free: sys.free_memory(),
+        available: sys.available_memory(),
+        used: sys.used_memory(),
+        swap_total: sys.total_swap(),
+        swap_used: sys.used_swap(),
+    }
+}
+
+fn disk_info(disks: &Disks) -> DiskInfo {
+    DiskInfo {
+        disks: disks
+            .list()
+            .iter()
+            .map(|d| Disk {
+                name: d.name().to_str().unwrap_or_default().to_string(),
+                kind: d.kind().to_string(),
+                file_system: d.file_system().to_str().unwrap_or_default().to_string(),
+                total_space: d.total_space(),
+                available_space: d.available_space(),
+            })
+            .collect(),
+    }
+}
+
+fn cpu_info(sys: &System) -> CpuInfo {
+    CpuInfo {
+        cpus: sys
+            .cpus()
+            .iter()
+            .map(|c| Cpu {
+                name: c.name().to_string(),
+                cpu_usage: c.cpu_usage(),
+                frequency: c.frequency(),
+            })
+            .collect(),
+    }
+}
+
+fn network_info(networks: &Networks, elapsed: f64) -> NetworkInfo {
+    let per_second = |bytes: u64| (bytes as f64 / elapsed.max(f64::EPSILON)) as u64;
+    NetworkInfo {
+        networks: networks
+            .list()
+            .iter()
+            .map(|(name, n)| Network {
+                name: name.clone(),
+                received: per_second(n.received()),
+                transmitted: per_second(n.transmitted()),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::docker::container::{ContainerCreationBody, HostConfig, RemoveOptions};
+use crate::docker::DockerClient;
+use crate::proto;
+use crate::schedule::Schedule;
+
+/// Directory inside the compose directory holding job definitions.
+pub const JOBS_DIR: &str = "jobs";
+
+/// Number of runs kept per job.
+const MAX_JOB_RUNS: usize = 10;
+
+/// A container run on a cron schedule, named after its file.
+#[derive(Deserialize, Clone, Debug)]
+pub struct JobDefinition {
+    pub image: String,
+    pub tag: String,
+    pub command: Vec<String>,
+    pub binds: Option<Vec<String>>,
+    pub env: Option<BTreeMap<String, String>>,
+    pub schedule: String,
+    /// Seconds a run may take before its container is killed.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    60 * 60
+}
+
+#[derive(Clone, Debug)]
+pub struct JobRun {
+    pub started: i64,
+    pub finished: i64,
+    pub exit_code: i64,
+    pub logs: Vec<String>,
+}
+
+impl From<&JobRun> for proto::JobRun {
+    fn from(r: &JobRun) -> Self {
+        Self {
+            started: r.started,
+            finished: r.finished,
+            exit_code: r.exit_code,
+            logs: r.logs.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Job {
+    pub definition: JobDefinition,
+    pub next_run: Option<i64>,
+    pub running: bool,
+    pub runs: VecDeque<JobRun>,
+    /// Why the definition file currently fails to parse, the job keeps its last good one.
+    pub error: Option<String>,
+}
+
+impl Job {
+    pub fn to_proto(&self, name: &str) -> proto::Job {
+        proto::Job {
+            name: name.to_string(),
+            image: format!("{}:{}", self.definition.image, self.definition.tag),
+            command: self.definition.command.clone(),
+            schedule: self.definition.schedule.clone(),
+            next_run: self.next_run,
+            running: self.running,
+            runs: self.runs.iter().map(proto::JobRun::from).collect(),
+            definition_error: self.error.clone().unwrap_or_default(),
+        }
+    }
+
+    pub fn record(&mut self, run: JobRun) {
+        if self.runs.len() == MAX_JOB_RUNS {
+            self.runs.pop_front();
+        }
+
+        self.runs.push_back(run);
+        self.running = false;
+    }
+}
+
+/// Job definitions keyed by file stem, with the parse error for invalid files.
+pub type Definitions = HashMap<String, std::result::Result<JobDefinition, String>>;
+
+/// Reads all job definitions from the jobs directory.
+pub fn load_definitions(compose_path: &Path) -> Result<Definitions> {
+    let jobs_path: PathBuf = compose_path.join(JOBS_DIR);
+    let mut definitions = HashMap::new();
+    if !jobs_path.is_dir() {
+        return Ok(definitions);
+    }
+
+    for dir_entry in jobs_path.read_dir()? {
+        let path = dir_entry?.path();
+        if path.is_dir() {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .with_context(|| format!("invalid job path {path:?}"))?
+            .to_string();
+
+        let definition = toml::from_str(&std::fs::read_to_string(&path)?)
+            .map_err(|err| format!("invalid job definition {path:?}: {err}"));
+        definitions.insert(name, definition);
+    }
+
+    Ok(definitions)
+}
+
+/// Adds new jobs, drops removed ones and reschedules jobs whose definition changed.
+/// A job whose file fails to parse keeps its last good definition and its runs.
+pub fn sync(jobs: &mut HashMap<String, Job>, definitions: Definitions, now: DateTime<Utc>) {
+    jobs.retain(|name, _| definitions.contains_key(name));
+
+    for (name, definition) in definitions {
+        let definition = match definition {
+            Ok(definition) => definition,
+            Err(err) => {
+                match jobs.get_mut(&name) {
+                    Some(job) if job.error.as_ref() == Some(&err) => {}
+                    Some(job) => {
+                        error!("job {name}: {err}, keeping the previous definition");
+                        job.error = Some(err);
+                    }
+                    None => error!("job {name}: {err}"),
+                }
+                continue;
+            }
+        };
+
+        let next_run = match Schedule::Cron(definition.schedule.clone()).next_run(now) {
+            Ok(next_run) => next_run,
+            Err(err) => {
+                error!("job {name}: {err:?}");
+                None
+            }
+        };
+
+        match jobs.get_mut(&name) {
+            Some(job) if job.definition.schedule == definition.schedule => {
+                job.definition = definition;
+                job.error = None;
+            }
+            Some(job) => {
+                job.definition = definition;
+                job.next_run = next_run;
+                job.error = None;
+            }
+            None => {
+                info!("Registered job {name}");
+                jobs.insert(
+                    name,
+                    Job {
+                        definition,
+                        next_run,
+                        running: false,
+                        runs: VecDeque::new(),
+                        error: None,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Runs the job in an ephemeral container and collects its exit code and logs.
+/// Failures to run the container are recorded as exit code -1.
+pub async fn run(docker: &DockerClient, name: &str, definition: &JobDefinition) -> JobRun {
+    let started = Utc::now().timestamp();
+    info!("Running job {name}");
+
+    let (exit_code, logs) = match run_container(docker, name, definition).await {
+        Ok(result) => result,
+        Err(err) => {
+            error!("job {name} failed: {err:?}");
+            (-1, vec![format!("{err:#}")])
+        }
+    };
+
+    JobRun {
+        started,
+        finished: Utc::now().timestamp(),
+        exit_code,
+        logs,
+    }
+}
+
+async fn run_container(
+    docker: &DockerClient,
+    name: &str,
+    definition: &JobDefinition,
+) -> Result<(i64, Vec<String>)> {
+    docker
+        .pull_image(&definition.image, &definition.tag)
+        .await?;
+
+    let body = ContainerCreationBody {
+        image: format!("{}:{}", definition.image, definition.tag),
+        command: Some(definition.command.clone()),
+        env: definition
+            .env
+            .as_ref()
+            .map(|env| env.iter().map(|(k, v)| format!("{k}={v}")).collect()),
+        host_config: HostConfig {
+            port_bindings: HashMap::new(),
+            binds: definition.binds.clone(),
+        },
+        networking_config: None,
+    };
+
+    let container_name = format!("job-{name}-{}", Utc::now().timestamp());
+    let id = docker.create_container(&container_name, body).await?;
+
+    let result = async {
+        docker.start_container(&id).await?;
+        let timeout = Duration::from_secs(definition.timeout);
+        let exit_code = match tokio::time::timeout(timeout, docker.wait_container(&id)).await {
+            Ok(exit_code) => exit_code?,
+            Err(_) => bail!("job timed out after {timeout:?}"),
+        };
+        let logs = docker.container_logs(&id).await?;
+        anyhow::Ok((exit_code, logs))
+    }
+    .await;
+
+    let options = RemoveOptions {
+        force: true,
+        volumes: false,
+    };
+    if let Err(err) = docker.remove_container(&id, options).await {
+        error!("Unable to remove job container {id}: {err:?}");
+    }
+
+    result
+}
@@ -1,20 +1,30 @@
 use config::Config;
+use docker::{DockerClient, Endpoint};
+use secret::SecretStore;
 use std::sync::{Arc, Mutex};
 use subscriber::{relay::LogRelay, StreamingLayer};
 use tonic::transport::Server;
-use tracing::{info, level_filters::LevelFilter};
+use tracing::{info, level_filters::LevelFilter, warn};
 use tracing_subscriber::{
     fmt::{self},
     prelude::*,
     EnvFilter,
 };
 
+mod compose;
 mod config;
 mod docker;
+mod error;
 mod info;
+mod job;
+mod schedule;
+mod secret;
 mod service;
 mod subscriber;
 
+#[cfg(test)]
+mod tests;
+
 mod proto {
     tonic::include_proto!("manager");
 }
@@ -23,13 +33,37 @@ mod proto {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let log_relay = setup_logging();
     let config = Config::new("config.toml".into())?;
+    let secret_store = Arc::new(Mutex::new(SecretStore::open(&config.secrets_path)?));
+    let mut docker = DockerClient::new(
+        Endpoint::resolve(config.docker_host.as_deref())?,
+        config.docker_tls.as_ref(),
+    )?;
 
     info!("Starting server at {:?}", config.address);
+    info!("Using docker at {:?}", docker.endpoint());
+    match docker.negotiate_api_version().await {
+        Ok(()) => info!("Using docker api version {}", docker.api_version()),
+        Err(err) => warn!(
+            "Unable to negotiate docker api version, using {}: {err:?}",
+            docker.api_version()
+        ),
+    }
 
     Server::builder()
-        .add_service(service::docker())
-        .add_service(service::system(config.clone(), log_relay))
-        .add_service(service::compose(config.clone()))
+        .add_service(service::docker(config.clone(), docker.clone()))
+        .add_service(service::system(config.clone(), docker.clone(), log_relay))
+        .add_service(service::compose(
+            config.clone(),
+            docker.clone(),
+            Arc::clone(&secret_store),
+        ))
+        .add_service(service::scheduler(
+            config.clone(),
+            docker.clone(),
+            Arc::clone(&secret_store),
+        )?)
+        .add_service(service::secrets(secret_store))
+        .add_service(service::jobs(config.clone(), docker))
         .serve(config.address)
         .await?;
 
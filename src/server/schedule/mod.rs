@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Error;
+use crate::proto;
+
+/// Number of outcomes kept per scheduled deploy.
+const MAX_OUTCOMES: usize = 20;
+
+/// Seconds a run may start late, e.g. after a server restart, before it counts as missed.
+const MAX_LATENESS: i64 = 10 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Schedule {
+    /// Runs once at the given unix timestamp.
+    Once(i64),
+    /// Standard five field cron expression, evaluated in UTC.
+    Cron(String),
+}
+
+impl Schedule {
+    /// Next run strictly after `after`, `None` once a one-shot is in the past.
+    pub fn next_run(&self, after: DateTime<Utc>) -> Result<Option<i64>> {
+        match self {
+            Schedule::Once(at) => Ok((*at > after.timestamp()).then_some(*at)),
+            Schedule::Cron(expression) => Ok(parse_cron(expression)?
+                .after(&after)
+                .next()
+                .map(|next| next.timestamp())),
+        }
+    }
+}
+
+/// The `cron` crate expects a leading seconds field, which crontab syntax lacks.
+fn parse_cron(expression: &str) -> Result<cron::Schedule> {
+    let expression = match expression.split_whitespace().count() {
+        5 => format!("0 {expression}"),
+        _ => expression.to_string(),
+    };
+
+    cron::Schedule::from_str(&expression)
+        .with_context(|| format!("invalid cron expression '{expression}'"))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Outcome {
+    pub time: i64,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduledDeploy {
+    pub id: String,
+    pub path: String,
+    pub variables: HashMap<String, String>,
+    pub schedule: Schedule,
+    pub next_run: Option<i64>,
+    pub outcomes: Vec<Outcome>,
+}
+
+impl ScheduledDeploy {
+    pub fn request(&self) -> proto::DeployRequest {
+        proto::DeployRequest {
+            path: self.path.clone(),
+            variables: self.variables.clone(),
+        }
+    }
+}
+
+impl From<&ScheduledDeploy> for proto::ScheduledDeploy {
+    fn from(s: &ScheduledDeploy) -> Self {
+        let schedule = match &s.schedule {
+            Schedule::Once(at) => proto::scheduled_deploy::Schedule::At(*at),
+            Schedule::Cron(expression) => {
+                proto::scheduled_deploy::Schedule::Cron(expression.clone())
+            }
+        };
+
+        Self {
+            id: s.id.clone(),
+            request: Some(s.request()),
+            next_run: s.next_run,
+            outcomes: s
+                .outcomes
+                .iter()
+                .map(|o| proto::DeployOutcome {
+                    time: o.time,
+                    success: o.success,
+                    message: o.message.clone(),
+                })
+                .collect(),
+            schedule: Some(schedule),
+        }
+    }
+}
+
+/// Scheduled deploys, persisted as JSON after every change.
+pub struct ScheduleStore {
+    path: PathBuf,
+    deploys: Vec<ScheduledDeploy>,
+}
+
+impl ScheduleStore {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let deploys = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .with_context(|| format!("invalid schedule file {path:?}"))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { path, deploys })
+    }
+
+    pub fn deploys(&self) -> &[ScheduledDeploy] {
+        &self.deploys
+    }
+
+    pub fn add(
+        &mut self,
+        request: proto::DeployRequest,
+        schedule: Schedule,
+    ) -> Result<ScheduledDeploy> {
+        let next_run = schedule
+            .next_run(Utc::now())
+            .map_err(|err| Error::InvalidArgument(format!("{err:#}")))?;
+        let Some(next_run) = next_run else {
+            return Err(Error::InvalidArgument(format!("schedule {schedule:?} never runs")).into());
+        };
+
+        let deploy = ScheduledDeploy {
+            id: Uuid::new_v4().to_string(),
+            path: request.path,
+            variables: request.variables,
+            schedule,
+            next_run: Some(next_run),
+            outcomes: Vec::new(),
+        };
+
+        self.deploys.push(deploy.clone());
+        self.save()?;
+
+        Ok(deploy)
+    }
+
+    pub fn cancel(&mut self, id: &str) -> Result<()> {
+        let len = self.deploys.len();
+        self.deploys.retain(|d| d.id != id);
+        if self.deploys.len() == len {
+            return Err(Error::NotFound(format!("unknown scheduled deploy {id}")).into());
+        }
+
+        self.save()
+    }
+
+    /// Returns the deploys due at `now` and advances their next run,
+    /// so a deploy is never picked up twice. Runs more than [`MAX_LATENESS`] overdue
+    /// are skipped and recorded as missed.
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Result<Vec<ScheduledDeploy>> {
+        let mut due = Vec::new();
+        let mut changed = false;
+        for deploy in &mut self.deploys {
+            let Some(next_run) = deploy.next_run.filter(|next| *next <= now.timestamp()) else {
+                continue;
+            };
+
+            if now.timestamp() - next_run > MAX_LATENESS {
+                push_outcome(
+                    deploy,
+                    Outcome {
+                        time: next_run,
+                        success: false,
+                        message: "missed".to_string(),
+                    },
+                );
+            } else {
+                due.push(deploy.clone());
+            }
+            deploy.next_run = deploy.schedule.next_run(now)?;
+            changed = true;
+        }
+
+        if changed {
+            self.save()?;
+        }
+
+        Ok(due)
+    }
+
+    /// Records the outcome of a run, unless the deploy got cancelled meanwhile.
+    pub fn record(&mut self, id: &str, outcome: Outcome) -> Result<()> {
+        let Some(deploy) = self.deploys.iter_mut().find(|d| d.id == id) else {
+            return Ok(());
+        };
+
+        push_outcome(deploy, outcome);
+
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(&self.deploys)?)?;
+        Ok(std::fs::rename(tmp_path, &self.path)?)
+    }
+}
+
+fn push_outcome(deploy: &mut ScheduledDeploy, outcome: Outcome) {
+    if deploy.outcomes.len() == MAX_OUTCOMES {
+        deploy.outcomes.remove(0);
+    }
+    deploy.outcomes.push(outcome);
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use tracing::info;
+
+use crate::error::Error;
+
+const MASTER_KEY_FILE: &str = "master.key";
+const STORE_FILE: &str = "secrets.bin";
+const NONCE_SIZE: usize = 12;
+
+/// Prefix referencing a stored secret from a service definition.
+pub const SECRET_PREFIX: &str = "secret:";
+
+/// Secrets encrypted at rest with a master key kept next to the store.
+/// Values are only ever handed out to deploys, never over RPC.
+pub struct SecretStore {
+    cipher: ChaCha20Poly1305,
+    store_path: PathBuf,
+}
+
+impl SecretStore {
+    /// Opens the store in `path`, generating a master key on first use.
+    pub fn open(path: &Path) -> Result<Self> {
+        std::fs::create_dir_all(path)?;
+
+        let key_path = path.join(MASTER_KEY_FILE);
+        if !key_path.exists() {
+            info!("Generating secret master key at {key_path:?}");
+            write_private(&key_path, &ChaCha20Poly1305::generate_key(&mut OsRng))?;
+        }
+
+        let key = std::fs::read(&key_path)?;
+        if key.len() != 32 {
+            bail!("invalid master key {key_path:?}");
+        }
+
+        Ok(Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+            store_path: path.join(STORE_FILE),
+        })
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        validate_name(name)?;
+
+        let mut secrets = self.load()?;
+        secrets.insert(name.to_string(), value.to_string());
+        self.save(&secrets)
+    }
+
+    pub fn get(&self, name: &str) -> Result<String> {
+        self.load()?
+            .remove(name)
+            .ok_or_else(|| Error::NotFound(format!("unknown secret '{name}'")).into())
+    }
+
+    pub fn names(&self) -> Result<Vec<String>> {
+        Ok(self.load()?.into_keys().collect())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let mut secrets = self.load()?;
+        if secrets.remove(name).is_none() {
+            return Err(Error::NotFound(format!("unknown secret '{name}'")).into());
+        }
+
+        self.save(&secrets)
+    }
+
+    /// Resolves `secret:NAME` references, returning other values unchanged.
+    pub fn resolve(&self, value: &str) -> Result<String> {
+        match value.strip_prefix(SECRET_PREFIX) {
+            Some(name) => self.get(name),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, String>> {
+        if !self.store_path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let data = std::fs::read(&self.store_path)?;
+        if data.len() < NONCE_SIZE {
+            bail!("corrupt secret store {:?}", self.store_path);
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("unable to decrypt {:?}", self.store_path))?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn save(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+            .map_err(|_| anyhow!("unable to encrypt secrets"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+
+        let tmp_path = self.store_path.with_extension("tmp");
+        write_private(&tmp_path, &data)?;
+        Ok(std::fs::rename(tmp_path, &self.store_path)?)
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+
+    if !valid {
+        return Err(Error::InvalidArgument(format!("invalid secret name '{name}'")).into());
+    }
+
+    Ok(())
+}
+
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+
+    Ok(file.write_all(data)?)
+}
@@ -1,29 +1,55 @@
-use serde::Deserialize;
 use std::collections::HashMap;
-use std::path::PathBuf;
-
-use anyhow::Context;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
 use tonic::{Request, Response, Status};
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
+use crate::compose::stack::StackDefinition;
+use crate::compose::template::{self, ENV_FILE};
+use crate::compose::{BuildDefinition, Definition, HealthCheck, Hook, ServiceDefinition};
 use crate::config::Config;
-use crate::docker;
-use crate::docker::container::{ContainerCreationBody, HostConfig, PortBinding};
-use crate::proto::DeployRequest;
+use crate::docker::container::{
+    ContainerCreationBody, EndpointSettings, HostConfig, NetworkingConfig, PortBinding,
+    RemoveOptions,
+};
+use crate::docker::DockerClient;
+use crate::error;
 use crate::proto::{
     compose_server::Compose, ComposeFile, ComposeFileDiff, DiffReply, DiffRequest, DiffResult,
     Empty, PushRequest,
 };
+use crate::proto::{DeployReply, DeployRequest};
+use crate::secret::{SecretStore, SECRET_PREFIX};
+
+type OutputStream = Pin<Box<dyn Stream<Item = Result<DeployReply, Status>> + Send>>;
 
-#[derive(Debug)]
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const LOG_TAIL_LINES: usize = 20;
+
+#[derive(Clone)]
 pub struct ComposeService {
     docker_compose_path: PathBuf,
+    docker: DockerClient,
+    secret_store: Arc<Mutex<SecretStore>>,
 }
 
-impl From<Config> for ComposeService {
-    fn from(config: Config) -> Self {
+impl ComposeService {
+    pub fn new(
+        config: Config,
+        docker: DockerClient,
+        secret_store: Arc<Mutex<SecretStore>>,
+    ) -> Self {
         Self {
             docker_compose_path: config.docker_compose_path,
+            docker,
+            secret_store,
         }
     }
 }
@@ -48,14 +74,28 @@ impl Compose for ComposeService {
         }
     }
 
-    async fn deploy(&self, req: Request<DeployRequest>) -> Result<Response<Empty>, Status> {
-        match self.handle_deploy(req.get_ref()).await {
-            Ok(_) => Ok(Response::new(Empty {})),
-            Err(err) => {
-                error!("deploy error: {err:?}");
-                Err(Status::from_error(err.into()))
-            }
-        }
+    type DeployStream = OutputStream;
+
+    async fn deploy(
+        &self,
+        req: Request<DeployRequest>,
+    ) -> Result<Response<Self::DeployStream>, Status> {
+        Ok(Response::new(self.stream_output(
+            req.into_inner(),
+            |service, req, output| async move { service.handle_deploy(&req, output).await },
+        )))
+    }
+
+    type UndeployStream = OutputStream;
+
+    async fn undeploy(
+        &self,
+        req: Request<DeployRequest>,
+    ) -> Result<Response<Self::UndeployStream>, Status> {
+        Ok(Response::new(self.stream_output(
+            req.into_inner(),
+            |service, req, output| async move { service.handle_undeploy(&req, output).await },
+        )))
     }
 }
 
@@ -71,7 +111,8 @@ impl ComposeService {
             let dir_entry = dir_entry?;
             let path = dir_entry.path();
 
-            if path.is_dir() {
+            // The server-side variables file is never pushed by clients
+            if path.is_dir() || path.ends_with(ENV_FILE) {
                 continue;
             }
 
@@ -120,7 +161,7 @@ impl ComposeService {
 
     fn got_removed(path: &PathBuf, files: &[ComposeFile]) -> bool {
         for file in files {
-            if PathBuf::from(file.path.clone()) == *path {
+            if Path::new(&file.path) == *path {
                 return false;
             }
         }
@@ -149,16 +190,195 @@ impl ComposeService {
         Ok(())
     }
 
-    async fn handle_deploy(&self, req: &DeployRequest) -> anyhow::Result<()> {
+    /// Runs `f` in the background, streaming its output and final error to the client.
+    fn stream_output<F, Fut>(&self, req: DeployRequest, f: F) -> OutputStream
+    where
+        F: FnOnce(ComposeService, DeployRequest, Sender<String>) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        let (tx, rx) = tokio::sync::mpsc::channel(128);
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let (output_tx, output_rx) = tokio::sync::mpsc::channel(128);
+            let (result, _) =
+                tokio::join!(f(service, req, output_tx), forward_output(output_rx, &tx));
+
+            if let Err(err) = result {
+                error!("deploy error: {err:?}");
+                if let Err(err) = tx.send(Err(error::to_status(err))).await {
+                    debug!("Dropping deploy error: {err:?}");
+                }
+            }
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
+
+    pub async fn handle_deploy(
+        &self,
+        req: &DeployRequest,
+        output: Sender<String>,
+    ) -> anyhow::Result<()> {
+        match self.load_definition(req)? {
+            Definition::Service(service_def) => {
+                self.deploy_service(*service_def, Vec::new(), &output).await
+            }
+            Definition::Stack(stack) => self.deploy_stack(stack, &output).await,
+        }
+    }
+
+    async fn handle_undeploy(
+        &self,
+        req: &DeployRequest,
+        output: Sender<String>,
+    ) -> anyhow::Result<()> {
+        match self.load_definition(req)? {
+            Definition::Service(service_def) => {
+                self.undeploy_service(&service_def.container_name, &output)
+                    .await
+            }
+            Definition::Stack(stack) => self.undeploy_stack(stack, &output).await,
+        }
+    }
+
+    /// Loads the requested definition, substituting variables from the `.env` file
+    /// in the compose directory overridden by the ones sent with the request.
+    fn load_definition(&self, req: &DeployRequest) -> anyhow::Result<Definition> {
+        let mut variables = template::load_env_file(&self.docker_compose_path.join(ENV_FILE))?;
+        variables.extend(req.variables.clone());
+
         let mut path = self.docker_compose_path.clone();
         path.push(req.path.clone());
 
-        let service_def: ServiceDefinition = toml::from_str(&std::fs::read_to_string(path)?)?;
-        info!("Deploying service");
+        Definition::load(&path, &variables)
+    }
+
+    async fn deploy_stack(
+        &self,
+        mut stack: StackDefinition,
+        output: &Sender<String>,
+    ) -> anyhow::Result<()> {
+        let order = stack.deploy_order()?;
+        report(output, format!("Deploying stack: {}", order.join(", "))).await;
+
+        for network in &stack.networks {
+            if !self.docker.network_exists(network).await? {
+                report(output, format!("Creating network {network}")).await;
+                self.docker.create_network(network).await?;
+            }
+        }
+
+        for volume in &stack.volumes {
+            report(output, format!("Creating volume {volume}")).await;
+            self.docker.create_volume(volume).await?;
+        }
+
+        for name in order {
+            let Some(stack_service) = stack.services.remove(&name) else {
+                continue;
+            };
+
+            let networks = stack
+                .networks
+                .iter()
+                .map(|network| {
+                    (
+                        network.clone(),
+                        EndpointSettings {
+                            aliases: vec![name.clone()],
+                        },
+                    )
+                })
+                .collect();
+
+            self.deploy_service(stack_service.service, networks, output)
+                .await
+                .with_context(|| format!("failed to deploy service '{name}'"))?;
+        }
+
+        Ok(())
+    }
+
+    async fn undeploy_stack(
+        &self,
+        stack: StackDefinition,
+        output: &Sender<String>,
+    ) -> anyhow::Result<()> {
+        let mut order = stack.deploy_order()?;
+        order.reverse();
+        report(output, format!("Undeploying stack: {}", order.join(", "))).await;
+
+        for name in order {
+            if let Some(stack_service) = stack.services.get(&name) {
+                self.undeploy_service(&stack_service.service.container_name, output)
+                    .await
+                    .with_context(|| format!("failed to undeploy service '{name}'"))?;
+            }
+        }
+
+        for network in &stack.networks {
+            report(output, format!("Removing network {network}")).await;
+            self.docker.remove_network(network).await?;
+        }
+
+        if !stack.volumes.is_empty() {
+            report(
+                output,
+                format!("Keeping volumes {}", stack.volumes.join(", ")),
+            )
+            .await;
+        }
+
+        Ok(())
+    }
+
+    async fn undeploy_service(&self, name: &str, output: &Sender<String>) -> anyhow::Result<()> {
+        let Some(existing) = self.docker.inspect_container(name).await? else {
+            report(output, format!("Container {name} is not deployed")).await;
+            return Ok(());
+        };
+
+        report(output, format!("Stopping container {name}")).await;
+        self.docker.stop_container(&existing.id, None).await?;
+
+        report(output, format!("Removing container {name}")).await;
+        self.docker
+            .remove_container(&existing.id, Default::default())
+            .await
+    }
 
-        info!("Pulling image {}:{}", service_def.image, service_def.tag);
-        docker::image::pull(&service_def.image, &service_def.tag).await?;
+    /// Deploys a single service attached to `networks`, if any.
+    async fn deploy_service(
+        &self,
+        service_def: ServiceDefinition,
+        mut networks: Vec<(String, EndpointSettings)>,
+        output: &Sender<String>,
+    ) -> anyhow::Result<()> {
+        report(
+            output,
+            format!("Deploying service {}", service_def.container_name),
+        )
+        .await;
+
+        let image = format!("{}:{}", service_def.image, service_def.tag);
+        match &service_def.build {
+            Some(build) => {
+                let context = self.build_context(build)?;
+                report(output, format!("Building image {image} from {context:?}")).await;
+                self.docker
+                    .build_image(&context, &build.dockerfile, &image, output)
+                    .await?;
+            }
+            None => {
+                report(output, format!("Pulling image {image}")).await;
+                self.docker
+                    .pull_image(&service_def.image, &service_def.tag)
+                    .await?;
+            }
+        }
 
+        let (env, files) = self.resolve_secrets(&service_def)?;
         let mut port_bindings = HashMap::new();
 
         for port in service_def.ports {
@@ -171,37 +391,433 @@ impl ComposeService {
             );
         }
 
+        // Daemons before API 1.44 accept only one network at create, the others are
+        // connected before the container starts.
+        let networking_config = (!networks.is_empty()).then(|| NetworkingConfig {
+            endpoints_config: HashMap::from([networks.remove(0)]),
+        });
+
         let body = ContainerCreationBody {
-            image: service_def.image,
-            command: service_def.command,
+            image,
+            command: service_def.command.map(|c| vec![c]),
+            env,
             host_config: HostConfig {
                 port_bindings,
                 binds: service_def.binds,
             },
+            networking_config,
+        };
+
+        let name = service_def.container_name;
+        let spec = ContainerSpec {
+            body,
+            networks,
+            files,
+            health: service_def.health,
+            post_deploy: service_def.post_deploy,
+        };
+
+        for (i, hook) in service_def.pre_deploy.iter().enumerate() {
+            let hook_name = format!("{name}-pre-deploy-{i}");
+            run_hook(&self.docker, hook, &hook_name, None, &spec, output)
+                .await
+                .context("pre-deploy hook failed")?;
+        }
+
+        let previous = self.retire_previous(&name, output).await?;
+
+        match self.start_container(&name, &spec, output).await {
+            Ok(()) => {
+                if let Some(previous) = previous {
+                    report(output, format!("Removing previous container {previous}")).await;
+                    self.docker
+                        .remove_container(&previous, Default::default())
+                        .await?;
+                }
+
+                report(output, format!("Deployed {name}")).await;
+                Ok(())
+            }
+            Err(err) => {
+                if let Some(previous) = previous {
+                    if let Err(restore_err) = self.restore_previous(&previous, &name, output).await
+                    {
+                        error!("restore error: {restore_err:?}");
+                        return Err(err.context(format!(
+                            "failed to restore previous container: {restore_err}"
+                        )));
+                    }
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    /// Moves an existing container named `name` out of the way and stops it,
+    /// so it can be restored if the new one does not come up. It is only stopped
+    /// once renamed, a failing rename leaves it serving.
+    async fn retire_previous(
+        &self,
+        name: &str,
+        output: &Sender<String>,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(existing) = self.docker.inspect_container(name).await? else {
+            return Ok(None);
         };
 
-        info!("Creating container {}", service_def.container_name);
-        let id = docker::container::create(&service_def.container_name, body).await?;
+        // Left behind by a crash or a failed restore, it would block the rename.
+        let previous = previous_name(name);
+        if let Some(leftover) = self.docker.inspect_container(&previous).await? {
+            report(output, format!("Removing leftover container {previous}")).await;
+            self.docker
+                .remove_container(
+                    &leftover.id,
+                    RemoveOptions {
+                        force: true,
+                        volumes: false,
+                    },
+                )
+                .await?;
+        }
+
+        self.docker
+            .rename_container(&existing.id, &previous)
+            .await?;
+        report(output, format!("Stopping previous container {name}")).await;
+        if let Err(err) = self.docker.stop_container(&existing.id, None).await {
+            if let Err(rename_err) = self.docker.rename_container(&existing.id, name).await {
+                error!("rename error: {rename_err:?}");
+            }
+            return Err(err);
+        }
+
+        Ok(Some(existing.id))
+    }
+
+    async fn restore_previous(
+        &self,
+        id: &str,
+        name: &str,
+        output: &Sender<String>,
+    ) -> anyhow::Result<()> {
+        report(output, format!("Restoring previous container {name}")).await;
+        self.docker.rename_container(id, name).await?;
+        self.docker.start_container(id).await
+    }
+
+    /// Resolves `secret:NAME` references in the environment and file mounts.
+    fn resolve_secrets(
+        &self,
+        service_def: &ServiceDefinition,
+    ) -> anyhow::Result<(Option<Vec<String>>, Vec<SecretFile>)> {
+        let store = self
+            .secret_store
+            .lock()
+            .map_err(|err| anyhow!("secret store lock error: {err}"))?;
+
+        let env = service_def
+            .env
+            .as_ref()
+            .map(|env| {
+                env.iter()
+                    .map(|(key, value)| Ok(format!("{key}={}", store.resolve(value)?)))
+                    .collect::<anyhow::Result<Vec<String>>>()
+            })
+            .transpose()?;
+
+        let mut files = Vec::new();
+        for file in service_def.files.iter().flatten() {
+            let Some(name) = file.source.strip_prefix(SECRET_PREFIX) else {
+                bail!("file source '{}' is not a secret reference", file.source);
+            };
+
+            files.push(SecretFile {
+                target: file.target.clone(),
+                content: store.get(name)?,
+                mode: file.mode,
+                uid: file.uid,
+                gid: file.gid,
+            });
+        }
+
+        Ok((env, files))
+    }
+
+    /// Creates and starts the container, then waits for it to become healthy
+    /// and runs the post-deploy hooks. A container that fails to come up is removed again.
+    async fn start_container(
+        &self,
+        name: &str,
+        spec: &ContainerSpec,
+        output: &Sender<String>,
+    ) -> anyhow::Result<()> {
+        report(output, format!("Creating container {name}")).await;
+        let id = self
+            .docker
+            .create_container(name, spec.body.clone())
+            .await?;
+
+        let mut result = anyhow::Ok(());
+        for (network, endpoint) in &spec.networks {
+            result = self.docker.connect_network(network, &id, endpoint).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        if result.is_ok() {
+            result = launch(&self.docker, &id, spec, output).await;
+        }
+        if result.is_ok() {
+            for (i, hook) in spec.post_deploy.iter().enumerate() {
+                let hook_name = format!("{name}-post-deploy-{i}");
+                result = run_hook(&self.docker, hook, &hook_name, Some(&id), spec, output)
+                    .await
+                    .context("post-deploy hook failed");
+                if result.is_err() {
+                    break;
+                }
+            }
+        }
+
+        if result.is_err() {
+            report(output, format!("Removing failed container {id}")).await;
+            // Forced, so a container that is still up goes too. A failed cleanup must not
+            // hide why the deploy failed.
+            let options = RemoveOptions {
+                force: true,
+                volumes: false,
+            };
+            if let Err(err) = self.docker.remove_container(&id, options).await {
+                error!("failed to remove container {name}: {err:?}");
+            }
+        }
+
+        result
+    }
+
+    /// Resolves the build context relative to the compose directory,
+    /// refusing contexts outside of it.
+    fn build_context(&self, build: &BuildDefinition) -> anyhow::Result<PathBuf> {
+        let root = self.docker_compose_path.canonicalize()?;
+        let context = root
+            .join(&build.context)
+            .canonicalize()
+            .with_context(|| format!("invalid build context {:?}", build.context))?;
+
+        if !context.starts_with(&root) {
+            bail!("build context {:?} is outside of {root:?}", build.context);
+        }
+
+        Ok(context)
+    }
+}
+
+async fn report(output: &Sender<String>, message: String) {
+    info!("{message}");
+    if let Err(err) = output.send(message).await {
+        debug!("Dropping deploy output: {err:?}");
+    }
+}
+
+async fn launch(
+    docker: &DockerClient,
+    id: &str,
+    spec: &ContainerSpec,
+    output: &Sender<String>,
+) -> anyhow::Result<()> {
+    if !spec.files.is_empty() {
+        report(output, format!("Copying {} secret files", spec.files.len())).await;
+        docker
+            .upload_to_container(id, "/", secret_archive(&spec.files)?)
+            .await?;
+    }
+
+    report(output, format!("Starting container {id}")).await;
+    docker.start_container(id).await?;
+
+    match &spec.health {
+        Some(health) => wait_until_healthy(docker, id, health, output).await,
+        None => Ok(()),
+    }
+}
 
-        info!("Starting container {}", id);
-        docker::container::start(&id).await
+/// Runs a hook and forwards its output. Exec hooks need the id of the started
+/// service container, one-off containers share its volumes, networks and environment.
+async fn run_hook(
+    docker: &DockerClient,
+    hook: &Hook,
+    name: &str,
+    container_id: Option<&str>,
+    spec: &ContainerSpec,
+    output: &Sender<String>,
+) -> anyhow::Result<()> {
+    let (exit_code, lines) = match hook {
+        Hook::Exec { command } => {
+            let Some(container_id) = container_id else {
+                bail!("exec hook {command:?} needs a running container");
+            };
+
+            report(output, format!("Running {command:?} in {container_id}")).await;
+            let result = docker.run_exec(container_id, command).await?;
+            (result.exit_code, result.output)
+        }
+        Hook::Container { image, command } => {
+            let mut body = spec.body.clone();
+            if let Some(image) = image {
+                body.image = image.clone();
+            }
+            body.command = Some(command.clone());
+            body.host_config.port_bindings.clear();
+
+            report(output, format!("Running {command:?} in {}", body.image)).await;
+            let id = docker.create_container(name, body).await?;
+            let result = run_to_completion(docker, &id, &spec.files).await;
+            // Forced, it may still run when waiting failed and would block the next deploy.
+            let options = RemoveOptions {
+                force: true,
+                volumes: false,
+            };
+            if let Err(err) = docker.remove_container(&id, options).await {
+                error!("failed to remove hook container {name}: {err:?}");
+            }
+            result?
+        }
+    };
+
+    for line in lines {
+        report(output, line).await;
+    }
+
+    if exit_code != 0 {
+        bail!("hook exited with code {exit_code}");
+    }
+
+    Ok(())
+}
+
+async fn run_to_completion(
+    docker: &DockerClient,
+    id: &str,
+    files: &[SecretFile],
+) -> anyhow::Result<(i64, Vec<String>)> {
+    if !files.is_empty() {
+        docker
+            .upload_to_container(id, "/", secret_archive(files)?)
+            .await?;
+    }
+
+    docker.start_container(id).await?;
+    let exit_code = docker.wait_container(id).await?;
+    let logs = docker.container_logs(id).await?;
+
+    Ok((exit_code, logs))
+}
+
+/// Everything needed to create and bring up the service container.
+struct ContainerSpec {
+    body: ContainerCreationBody,
+    /// Networks joined after create, on top of the one in `body`.
+    networks: Vec<(String, EndpointSettings)>,
+    files: Vec<SecretFile>,
+    health: Option<HealthCheck>,
+    post_deploy: Vec<Hook>,
+}
+
+/// A resolved secret to be placed into a container.
+struct SecretFile {
+    target: PathBuf,
+    content: String,
+    mode: u32,
+    uid: u64,
+    gid: u64,
+}
+
+/// Packs secret files into a tar archive rooted at `/` with their mode and owner.
+fn secret_archive(files: &[SecretFile]) -> anyhow::Result<Vec<u8>> {
+    let mut archive = tar::Builder::new(Vec::new());
+
+    for file in files {
+        if !file.target.is_absolute() {
+            bail!("file target {:?} has to be absolute", file.target);
+        }
+
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.content.len() as u64);
+        header.set_mode(file.mode);
+        header.set_uid(file.uid);
+        header.set_gid(file.gid);
+        header.set_cksum();
+        archive.append_data(
+            &mut header,
+            file.target.strip_prefix("/")?,
+            file.content.as_bytes(),
+        )?;
     }
+
+    Ok(archive.into_inner()?)
 }
 
-#[derive(Deserialize, Clone, Debug)]
-struct PortMapping {
-    protocol: String,
-    host_ip: String,
-    host_port: String,
-    container_port: String,
+/// Polls the container until Docker reports it healthy or, for images
+/// without a healthcheck, until it has kept running for the grace period.
+async fn wait_until_healthy(
+    docker: &DockerClient,
+    id: &str,
+    health: &HealthCheck,
+    output: &Sender<String>,
+) -> anyhow::Result<()> {
+    report(
+        output,
+        format!("Waiting for container {id} to become healthy"),
+    )
+    .await;
+
+    let started = Instant::now();
+    let timeout = Duration::from_secs(health.timeout);
+    let grace_period = Duration::from_secs(health.grace_period);
+
+    let failure = loop {
+        let Some(container) = docker.inspect_container(id).await? else {
+            bail!("container {id} disappeared");
+        };
+
+        let state = container.state;
+        if !state.running {
+            break format!(
+                "container is {} (exit code {})",
+                state.status, state.exit_code
+            );
+        }
+
+        // Podman reports an empty health status for images without a healthcheck.
+        match state.health.map(|h| h.status).filter(|s| !s.is_empty()) {
+            Some(status) if status == "healthy" => return Ok(()),
+            Some(status) if status == "unhealthy" => break "container is unhealthy".to_string(),
+            Some(_) => {}
+            None if started.elapsed() >= grace_period => return Ok(()),
+            None => {}
+        }
+
+        if started.elapsed() >= timeout {
+            break format!("container not healthy after {timeout:?}");
+        }
+
+        tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+    };
+
+    let logs = docker.container_logs(id).await.unwrap_or_default();
+    let tail = logs[logs.len().saturating_sub(LOG_TAIL_LINES)..].join("\n");
+    bail!("{failure}, last log lines:\n{tail}")
 }
 
-#[derive(Deserialize, Clone, Debug)]
-struct ServiceDefinition {
-    image: String,
-    tag: String,
-    container_name: String,
-    command: Option<String>,
-    binds: Option<Vec<String>>,
-    ports: Vec<PortMapping>,
+fn previous_name(name: &str) -> String {
+    format!("{name}-previous")
+}
+
+async fn forward_output(mut rx: Receiver<String>, tx: &Sender<Result<DeployReply, Status>>) {
+    while let Some(output) = rx.recv().await {
+        if let Err(err) = tx.send(Ok(DeployReply { output })).await {
+            debug!("Dropping deploy output: {err:?}");
+        }
+    }
 }
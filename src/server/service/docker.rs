@@ -1,12 +1,38 @@
+use std::pin::Pin;
+
 use anyhow::Result;
-use tonic::{Request, Response, Status};
+use http_body_util::BodyExt;
+use hyper::body::{Bytes, Incoming};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{debug, error, info};
+
+use crate::config::Config;
+use crate::proto::{self, exec_input, exec_output, upload_chunk, ExecInput, ExecOutput};
 
-use crate::proto;
+use crate::docker::{client::BoxError, container::RemoveOptions, event::EventReader, DockerClient};
+use crate::error;
 
-use crate::docker::container;
+#[derive(Debug)]
+pub struct DockerService {
+    docker: DockerClient,
+    max_download_size: u64,
+    max_upload_size: u64,
+    max_export_size: Option<u64>,
+}
 
-#[derive(Debug, Default)]
-pub struct DockerService {}
+impl DockerService {
+    pub fn new(config: Config, docker: DockerClient) -> Self {
+        Self {
+            docker,
+            max_download_size: config.max_download_size,
+            max_upload_size: config.max_upload_size,
+            max_export_size: config.max_export_size,
+        }
+    }
+}
 
 #[tonic::async_trait]
 impl proto::docker_server::Docker for DockerService {
@@ -14,32 +40,493 @@ impl proto::docker_server::Docker for DockerService {
         &self,
         request: Request<proto::ContainerIdentifier>,
     ) -> Result<Response<proto::Empty>, Status> {
-        container::start(&request.get_ref().id)
+        self.docker
+            .start_container(&request.get_ref().id)
             .await
-            .map_err(|e| Status::from_error(e.into()))?;
+            .map_err(error::to_status)?;
 
         Ok(Response::new(proto::Empty {}))
     }
 
     async fn stop_container(
         &self,
-        request: Request<proto::ContainerIdentifier>,
+        request: Request<proto::StopContainerRequest>,
     ) -> Result<Response<proto::Empty>, Status> {
-        container::stop(&request.get_ref().id)
+        let request = request.get_ref();
+        self.docker
+            .stop_container(&request.id, request.timeout)
             .await
-            .map_err(|e| Status::from_error(e.into()))?;
+            .map_err(error::to_status)?;
 
         Ok(Response::new(proto::Empty {}))
     }
 
     async fn remove_container(
+        &self,
+        request: Request<proto::RemoveContainerRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.get_ref();
+        let options = RemoveOptions {
+            force: request.force,
+            volumes: request.remove_volumes,
+        };
+        self.docker
+            .remove_container(&request.id, options)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn restart_container(
+        &self,
+        request: Request<proto::StopContainerRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.get_ref();
+        self.docker
+            .restart_container(&request.id, request.timeout)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn pause_container(
+        &self,
+        request: Request<proto::ContainerIdentifier>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        self.docker
+            .pause_container(&request.get_ref().id)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn unpause_container(
         &self,
         request: Request<proto::ContainerIdentifier>,
     ) -> Result<Response<proto::Empty>, Status> {
-        container::remove(&request.get_ref().id)
+        self.docker
+            .unpause_container(&request.get_ref().id)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn kill_container(
+        &self,
+        request: Request<proto::KillContainerRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.get_ref();
+        self.docker
+            .kill_container(&request.id, &request.signal)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn rename_container(
+        &self,
+        request: Request<proto::RenameContainerRequest>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let request = request.get_ref();
+        if request.name.is_empty() {
+            return Err(Status::invalid_argument("name can't be empty"));
+        }
+
+        self.docker
+            .rename_container(&request.id, &request.name)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::Empty {}))
+    }
+
+    async fn top(
+        &self,
+        request: Request<proto::TopRequest>,
+    ) -> Result<Response<proto::ProcessList>, Status> {
+        let request = request.get_ref();
+        let top = self
+            .docker
+            .top(&request.id, &request.ps_args)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(top.into()))
+    }
+
+    async fn changes(
+        &self,
+        request: Request<proto::ContainerIdentifier>,
+    ) -> Result<Response<proto::FilesystemChanges>, Status> {
+        let changes = self
+            .docker
+            .container_changes(&request.get_ref().id)
+            .await
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(proto::FilesystemChanges {
+            changes: changes.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn commit(
+        &self,
+        request: Request<proto::CommitRequest>,
+    ) -> Result<Response<proto::CommitReply>, Status> {
+        let request = request.get_ref();
+        if request.repository.is_empty() {
+            return Err(Status::invalid_argument("repository can't be empty"));
+        }
+
+        let image_id = self
+            .docker
+            .commit_container(
+                &request.id,
+                &request.repository,
+                &request.tag,
+                &request.comment,
+                request.pause,
+            )
+            .await
+            .map_err(error::to_status)?;
+        info!(
+            "Committed container {} to {}:{}",
+            request.id, request.repository, request.tag
+        );
+
+        Ok(Response::new(proto::CommitReply { image_id }))
+    }
+
+    type ExportStream = Pin<Box<dyn Stream<Item = Result<proto::ArchiveChunk, Status>> + Send>>;
+
+    async fn export(
+        &self,
+        request: Request<proto::ContainerIdentifier>,
+    ) -> Result<Response<Self::ExportStream>, Status> {
+        let id = &request.get_ref().id;
+        let body = self
+            .docker
+            .export_container(id)
+            .await
+            .map_err(error::to_status)?;
+        info!("Exporting container {id}");
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let limit = self.max_export_size;
+        tokio::spawn(async move {
+            forward_archive(body, limit, tx).await;
+        });
+
+        let output_stream = ReceiverStream::new(rx);
+        Ok(Response::new(Box::pin(output_stream) as Self::ExportStream))
+    }
+
+    type DownloadArchiveStream =
+        Pin<Box<dyn Stream<Item = Result<proto::ArchiveChunk, Status>> + Send>>;
+
+    async fn download_archive(
+        &self,
+        request: Request<proto::ArchiveRequest>,
+    ) -> Result<Response<Self::DownloadArchiveStream>, Status> {
+        let request = request.into_inner();
+        let body = self
+            .docker
+            .download_from_container(&request.id, &request.path)
             .await
-            .map_err(|e| Status::from_error(e.into()))?;
+            .map_err(error::to_status)?;
+        info!("Downloading {} from container {}", request.path, request.id);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let limit = Some(self.max_download_size);
+        tokio::spawn(async move {
+            forward_archive(body, limit, tx).await;
+        });
+
+        let output_stream = ReceiverStream::new(rx);
+        Ok(Response::new(
+            Box::pin(output_stream) as Self::DownloadArchiveStream
+        ))
+    }
+
+    async fn upload_archive(
+        &self,
+        request: Request<Streaming<proto::UploadChunk>>,
+    ) -> Result<Response<proto::Empty>, Status> {
+        let mut input = request.into_inner();
+        let target = match input.message().await? {
+            Some(proto::UploadChunk {
+                chunk: Some(upload_chunk::Chunk::Target(target)),
+            }) => target,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "upload has to begin with a target",
+                ))
+            }
+        };
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let (forwarded, uploaded) = tokio::join!(
+            forward_upload(&mut input, self.max_upload_size, tx),
+            self.docker
+                .upload_stream_to_container(&target.id, &target.path, rx)
+        );
+        // A failed forward aborted the upload, its error says why.
+        let size = forwarded?;
+        uploaded.map_err(error::to_status)?;
+
+        info!(
+            "Uploaded {size} bytes to {} in container {}",
+            target.path, target.id
+        );
 
         Ok(Response::new(proto::Empty {}))
     }
+
+    type EventsStream = Pin<Box<dyn Stream<Item = Result<proto::DockerEvent, Status>> + Send>>;
+
+    async fn events(
+        &self,
+        _request: Request<proto::Empty>,
+    ) -> Result<Response<Self::EventsStream>, Status> {
+        let reader = self.docker.events().await.map_err(error::to_status)?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(128);
+        tokio::spawn(async move {
+            forward_events(reader, tx).await;
+        });
+
+        let output_stream = ReceiverStream::new(rx);
+        Ok(Response::new(Box::pin(output_stream) as Self::EventsStream))
+    }
+
+    type ExecStream = Pin<Box<dyn Stream<Item = Result<ExecOutput, Status>> + Send>>;
+
+    async fn exec(
+        &self,
+        request: Request<Streaming<ExecInput>>,
+    ) -> Result<Response<Self::ExecStream>, Status> {
+        let mut input = request.into_inner();
+        let start = match input.message().await? {
+            Some(ExecInput {
+                input: Some(exec_input::Input::Start(start)),
+            }) => start,
+            _ => return Err(Status::invalid_argument("exec has to begin with a start")),
+        };
+
+        let session = self
+            .docker
+            .start_interactive_exec(&start.container_id, &start.command)
+            .await
+            .map_err(error::to_status)?;
+        info!(
+            "Started exec {} in container {}",
+            session.id, start.container_id
+        );
+
+        if let Some(size) = start.size {
+            if let Err(err) = self
+                .docker
+                .resize_exec(&session.id, size.rows, size.columns)
+                .await
+            {
+                debug!("Unable to resize exec {}: {err:?}", session.id);
+            }
+        }
+
+        let (reader, writer) = tokio::io::split(session.io);
+        let (tx, rx) = tokio::sync::mpsc::channel(128);
+
+        let id = session.id.clone();
+        let docker = self.docker.clone();
+        tokio::spawn(async move {
+            forward_input(&docker, &id, input, writer).await;
+        });
+
+        let id = session.id;
+        let docker = self.docker.clone();
+        tokio::spawn(async move {
+            forward_output(&docker, &id, reader, tx).await;
+        });
+
+        let output_stream = ReceiverStream::new(rx);
+        Ok(Response::new(Box::pin(output_stream) as Self::ExecStream))
+    }
+}
+
+async fn forward_events(mut reader: EventReader, tx: Sender<Result<proto::DockerEvent, Status>>) {
+    loop {
+        let result = match reader.next().await {
+            Ok(Some(event)) => Ok(proto::DockerEvent::from(event)),
+            Ok(None) => break,
+            Err(err) => Err(error::to_status(err)),
+        };
+
+        let failed = result.is_err();
+        if tx.send(result).await.is_err() {
+            debug!("Event client went away");
+            return;
+        }
+        if failed {
+            return;
+        }
+    }
+}
+
+/// Streams the archive body to the client, aborting once it grows past `limit` if set.
+async fn forward_archive(
+    mut body: Incoming,
+    limit: Option<u64>,
+    tx: Sender<Result<proto::ArchiveChunk, Status>>,
+) {
+    let mut total = 0;
+    while let Some(frame) = body.frame().await {
+        let result = match frame {
+            Ok(frame) => match frame.into_data() {
+                Ok(data) => {
+                    total += data.len() as u64;
+                    if let Some(limit) = limit.filter(|limit| total > *limit) {
+                        Err(Status::resource_exhausted(format!(
+                            "download exceeds the limit of {limit} bytes"
+                        )))
+                    } else {
+                        Ok(proto::ArchiveChunk {
+                            data: data.to_vec(),
+                        })
+                    }
+                }
+                Err(_) => continue,
+            },
+            Err(err) => Err(Status::unavailable(err.to_string())),
+        };
+
+        let failed = result.is_err();
+        if tx.send(result).await.is_err() {
+            debug!("Archive download client went away");
+            return;
+        }
+        if failed {
+            return;
+        }
+    }
+}
+
+/// Passes the uploaded archive on to Docker as it arrives, aborting the upload once it
+/// grows past `limit` or the client fails. Returns the number of bytes forwarded.
+async fn forward_upload(
+    input: &mut Streaming<proto::UploadChunk>,
+    limit: u64,
+    tx: Sender<Result<Bytes, BoxError>>,
+) -> Result<u64, Status> {
+    let mut total = 0;
+    loop {
+        let data = match input.message().await {
+            Ok(Some(proto::UploadChunk {
+                chunk: Some(upload_chunk::Chunk::Data(data)),
+            })) => data,
+            Ok(Some(_)) => continue,
+            Ok(None) => return Ok(total),
+            Err(status) => {
+                let _ = tx.send(Err("upload aborted by the client".into())).await;
+                return Err(status);
+            }
+        };
+
+        total += data.len() as u64;
+        if total > limit {
+            let _ = tx.send(Err("upload exceeds the limit".into())).await;
+            return Err(Status::resource_exhausted(format!(
+                "upload exceeds the limit of {limit} bytes"
+            )));
+        }
+        if tx.send(Ok(Bytes::from(data))).await.is_err() {
+            debug!("Docker stopped reading the upload");
+            return Ok(total);
+        }
+    }
+}
+
+/// Writes client input to the exec until the client closes its stream.
+async fn forward_input(
+    docker: &DockerClient,
+    id: &str,
+    mut input: Streaming<ExecInput>,
+    mut writer: impl AsyncWrite + Unpin,
+) {
+    loop {
+        let message = match input.message().await {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(err) => {
+                debug!("exec {id} input error: {err:?}");
+                break;
+            }
+        };
+
+        match message.input {
+            Some(exec_input::Input::Stdin(data)) => {
+                if let Err(err) = writer.write_all(&data).await {
+                    error!("exec {id} write error: {err:?}");
+                    break;
+                }
+            }
+            Some(exec_input::Input::Resize(size)) => {
+                if let Err(err) = docker.resize_exec(id, size.rows, size.columns).await {
+                    debug!("Unable to resize exec {id}: {err:?}");
+                }
+            }
+            Some(exec_input::Input::Start(_)) | None => {}
+        }
+    }
+
+    if let Err(err) = writer.shutdown().await {
+        debug!("exec {id} shutdown error: {err:?}");
+    }
+}
+
+/// Streams exec output to the client, followed by the exit code.
+async fn forward_output(
+    docker: &DockerClient,
+    id: &str,
+    mut reader: impl AsyncRead + Unpin,
+    tx: Sender<Result<ExecOutput, Status>>,
+) {
+    let mut buf = vec![0; 4096];
+    loop {
+        let output = match reader.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(n) => exec_output::Output::Stdout(buf[..n].to_vec()),
+            Err(err) => {
+                error!("exec {id} read error: {err:?}");
+                break;
+            }
+        };
+
+        if tx
+            .send(Ok(ExecOutput {
+                output: Some(output),
+            }))
+            .await
+            .is_err()
+        {
+            debug!("exec {id} client went away");
+            return;
+        }
+    }
+
+    let result = match docker.exec_exit_code(id).await {
+        Ok(exit_code) => Ok(ExecOutput {
+            output: Some(exec_output::Output::ExitCode(exit_code)),
+        }),
+        Err(err) => Err(error::to_status(err)),
+    };
+
+    if let Err(err) = tx.send(result).await {
+        debug!("Dropping exec {id} exit code: {err:?}");
+    }
+    info!("Exec {id} finished");
 }
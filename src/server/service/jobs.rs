@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+use crate::{
+    config::Config,
+    docker::DockerClient,
+    error::{self, Error},
+    job::{self, Job},
+    proto::{jobs_server::Jobs, Empty, JobIdentifier, JobList},
+    schedule::Schedule,
+};
+
+const JOB_INTERVAL: Duration = Duration::from_secs(10);
+
+type JobMap = Arc<Mutex<HashMap<String, Job>>>;
+
+pub struct JobsService {
+    jobs: JobMap,
+    docker: DockerClient,
+}
+
+impl JobsService {
+    pub fn new(config: Config, docker: DockerClient) -> Self {
+        let jobs = Arc::new(Mutex::new(HashMap::new()));
+
+        let j = Arc::clone(&jobs);
+        let d = docker.clone();
+        tokio::task::spawn(async move {
+            run_jobs(config.docker_compose_path, d, j).await;
+        });
+
+        Self { jobs, docker }
+    }
+}
+
+async fn run_jobs(compose_path: PathBuf, docker: DockerClient, jobs: JobMap) {
+    info!("Starting job runner with interval {JOB_INTERVAL:?}");
+    loop {
+        if let Err(err) = tick(&compose_path, &docker, &jobs) {
+            error!("job runner error: {err:?}");
+        }
+
+        tokio::time::sleep(JOB_INTERVAL).await;
+    }
+}
+
+/// Syncs the job definitions and starts every job that is due and not already running.
+fn tick(compose_path: &Path, docker: &DockerClient, jobs: &JobMap) -> Result<()> {
+    let definitions = job::load_definitions(compose_path)?;
+    let now = Utc::now();
+
+    let mut locked = jobs
+        .lock()
+        .map_err(|err| anyhow!("jobs lock error: {err}"))?;
+    job::sync(&mut locked, definitions, now);
+
+    let due: Vec<String> = locked
+        .iter()
+        .filter(|(_, job)| !job.running && job.next_run.is_some_and(|n| n <= now.timestamp()))
+        .map(|(name, _)| name.clone())
+        .collect();
+    drop(locked);
+
+    for name in due {
+        if let Err(err) = start(docker, jobs, &name) {
+            error!("Unable to start job {name}: {err:?}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Marks the job as running, advances its schedule and runs it in the background.
+fn start(docker: &DockerClient, jobs: &JobMap, name: &str) -> Result<()> {
+    let definition = {
+        let mut locked = jobs
+            .lock()
+            .map_err(|err| anyhow!("jobs lock error: {err}"))?;
+        let Some(job) = locked.get_mut(name) else {
+            return Err(Error::NotFound(format!("unknown job '{name}'")).into());
+        };
+
+        if job.running {
+            return Err(
+                Error::FailedPrecondition(format!("job '{name}' is already running")).into(),
+            );
+        }
+
+        job.running = true;
+        job.next_run = Schedule::Cron(job.definition.schedule.clone()).next_run(Utc::now())?;
+        job.definition.clone()
+    };
+
+    let jobs = Arc::clone(jobs);
+    let docker = docker.clone();
+    let name = name.to_string();
+    tokio::spawn(async move {
+        let run = job::run(&docker, &name, &definition).await;
+        match jobs.lock() {
+            Ok(mut jobs) => {
+                if let Some(job) = jobs.get_mut(&name) {
+                    job.record(run);
+                }
+            }
+            Err(err) => error!("jobs lock error: {err:?}"),
+        }
+    });
+
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Jobs for JobsService {
+    async fn list_jobs(&self, _: Request<Empty>) -> Result<Response<JobList>, Status> {
+        let jobs = match self.jobs.lock() {
+            Ok(jobs) => {
+                let mut jobs: Vec<_> = jobs.iter().map(|(name, job)| job.to_proto(name)).collect();
+                jobs.sort_by(|a, b| a.name.cmp(&b.name));
+                jobs
+            }
+            Err(err) => {
+                error!("{err:?}");
+                return Err(Status::from_error("Lock is poisoned".into()));
+            }
+        };
+
+        Ok(Response::new(JobList { jobs }))
+    }
+
+    async fn run_job(&self, request: Request<JobIdentifier>) -> Result<Response<Empty>, Status> {
+        start(&self.docker, &self.jobs, &request.get_ref().name).map_err(error::to_status)?;
+        Ok(Response::new(Empty {}))
+    }
+}
@@ -1,29 +1,66 @@
 use std::sync::{Arc, Mutex};
 
 use crate::{
+    docker::DockerClient,
     proto::{
-        compose_server::ComposeServer, docker_server::DockerServer, system_server::SystemServer,
+        compose_server::ComposeServer, docker_server::DockerServer, jobs_server::JobsServer,
+        scheduler_server::SchedulerServer, secrets_server::SecretsServer,
+        system_server::SystemServer,
     },
+    secret::SecretStore,
     subscriber::relay::LogRelay,
 };
 use compose::ComposeService;
 use docker::DockerService;
+use jobs::JobsService;
+use scheduler::SchedulerService;
+use secrets::SecretsService;
 use system::SystemService;
 
 use crate::config::Config;
 
 mod compose;
 mod docker;
+mod jobs;
+mod scheduler;
+mod secrets;
 mod system;
 
-pub fn docker() -> DockerServer<DockerService> {
-    DockerServer::new(DockerService::default())
+pub fn docker(config: Config, docker: DockerClient) -> DockerServer<DockerService> {
+    DockerServer::new(DockerService::new(config, docker))
 }
 
-pub fn system(config: Config, log_relay: Arc<Mutex<LogRelay>>) -> SystemServer<SystemService> {
-    SystemServer::new(SystemService::new(config, log_relay))
+pub fn system(
+    config: Config,
+    docker: DockerClient,
+    log_relay: Arc<Mutex<LogRelay>>,
+) -> SystemServer<SystemService> {
+    SystemServer::new(SystemService::new(config, docker, log_relay))
 }
 
-pub fn compose(config: Config) -> ComposeServer<ComposeService> {
-    ComposeServer::new(ComposeService::from(config))
+pub fn compose(
+    config: Config,
+    docker: DockerClient,
+    secret_store: Arc<Mutex<SecretStore>>,
+) -> ComposeServer<ComposeService> {
+    ComposeServer::new(ComposeService::new(config, docker, secret_store))
+}
+
+pub fn secrets(secret_store: Arc<Mutex<SecretStore>>) -> SecretsServer<SecretsService> {
+    SecretsServer::new(SecretsService::new(secret_store))
+}
+
+pub fn scheduler(
+    config: Config,
+    docker: DockerClient,
+    secret_store: Arc<Mutex<SecretStore>>,
+) -> anyhow::Result<SchedulerServer<SchedulerService>> {
+    let compose = ComposeService::new(config.clone(), docker, secret_store);
+    Ok(SchedulerServer::new(SchedulerService::new(
+        config, compose,
+    )?))
+}
+
+pub fn jobs(config: Config, docker: DockerClient) -> JobsServer<JobsService> {
+    JobsServer::new(JobsService::new(config, docker))
 }
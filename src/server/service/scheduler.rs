@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+use crate::{
+    config::Config,
+    error::{self, Error},
+    proto::{
+        schedule_deploy_request, scheduler_server::Scheduler, Empty, ScheduleDeployRequest,
+        ScheduledDeploy, ScheduledDeployIdentifier, ScheduledDeployList,
+    },
+    schedule::{self, Outcome, Schedule, ScheduleStore},
+};
+
+use super::compose::ComposeService;
+
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct SchedulerService {
+    store: Arc<Mutex<ScheduleStore>>,
+}
+
+impl SchedulerService {
+    pub fn new(config: Config, compose: ComposeService) -> Result<Self> {
+        let store = Arc::new(Mutex::new(ScheduleStore::open(config.schedule_path)?));
+
+        let s = Arc::clone(&store);
+        tokio::task::spawn(async move {
+            run_scheduler(s, compose).await;
+        });
+
+        Ok(Self { store })
+    }
+
+    fn with_store<T>(&self, f: impl FnOnce(&mut ScheduleStore) -> Result<T>) -> Result<T> {
+        let mut store = self
+            .store
+            .lock()
+            .map_err(|err| anyhow!("schedule lock error: {err}"))?;
+
+        f(&mut store).inspect_err(|err| error!("schedule error: {err:?}"))
+    }
+}
+
+async fn run_scheduler(store: Arc<Mutex<ScheduleStore>>, compose: ComposeService) {
+    info!("Starting deploy scheduler with interval {SCHEDULER_INTERVAL:?}");
+    loop {
+        tokio::time::sleep(SCHEDULER_INTERVAL).await;
+
+        let due = match store.lock() {
+            Ok(mut store) => store.take_due(Utc::now()),
+            Err(err) => {
+                error!("schedule lock error: {err:?}");
+                continue;
+            }
+        };
+
+        match due {
+            Ok(due) => {
+                for deploy in due {
+                    run_scheduled(&deploy, &store, &compose).await;
+                }
+            }
+            Err(err) => error!("scheduler error: {err:?}"),
+        }
+    }
+}
+
+async fn run_scheduled(
+    deploy: &schedule::ScheduledDeploy,
+    store: &Arc<Mutex<ScheduleStore>>,
+    compose: &ComposeService,
+) {
+    info!("Running scheduled deploy {} of {}", deploy.id, deploy.path);
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(128);
+    let collect_output = async move {
+        let mut last_line = String::new();
+        while let Some(line) = rx.recv().await {
+            last_line = line;
+        }
+        last_line
+    };
+
+    let request = deploy.request();
+    let (result, last_line) = tokio::join!(compose.handle_deploy(&request, tx), collect_output);
+
+    let outcome = match result {
+        Ok(()) => Outcome {
+            time: Utc::now().timestamp(),
+            success: true,
+            message: last_line,
+        },
+        Err(err) => {
+            error!("scheduled deploy {} failed: {err:?}", deploy.id);
+            Outcome {
+                time: Utc::now().timestamp(),
+                success: false,
+                message: format!("{err:#}"),
+            }
+        }
+    };
+
+    match store.lock() {
+        Ok(mut store) => {
+            if let Err(err) = store.record(&deploy.id, outcome) {
+                error!("schedule record error: {err:?}");
+            }
+        }
+        Err(err) => error!("schedule lock error: {err:?}"),
+    }
+}
+
+#[tonic::async_trait]
+impl Scheduler for SchedulerService {
+    async fn schedule_deploy(
+        &self,
+        request: Request<ScheduleDeployRequest>,
+    ) -> Result<Response<ScheduledDeploy>, Status> {
+        let req = request.into_inner();
+        let deploy = self
+            .with_store(|store| {
+                let missing = |field: &str| Error::InvalidArgument(format!("no {field}"));
+                let deploy_request = req.request.ok_or_else(|| missing("deploy request"))?;
+                let schedule = match req.schedule.ok_or_else(|| missing("schedule"))? {
+                    schedule_deploy_request::Schedule::At(at) => Schedule::Once(at),
+                    schedule_deploy_request::Schedule::Cron(expression) => {
+                        Schedule::Cron(expression)
+                    }
+                };
+
+                store.add(deploy_request, schedule)
+            })
+            .map_err(error::to_status)?;
+
+        info!("Scheduled deploy {} of {}", deploy.id, deploy.path);
+        Ok(Response::new(ScheduledDeploy::from(&deploy)))
+    }
+
+    async fn list_scheduled(
+        &self,
+        _: Request<Empty>,
+    ) -> Result<Response<ScheduledDeployList>, Status> {
+        let deploys = self
+            .with_store(|store| Ok(store.deploys().iter().map(ScheduledDeploy::from).collect()))
+            .map_err(error::to_status)?;
+
+        Ok(Response::new(ScheduledDeployList { deploys }))
+    }
+
+    async fn cancel_scheduled(
+        &self,
+        request: Request<ScheduledDeployIdentifier>,
+    ) -> Result<Response<Empty>, Status> {
+        let id = &request.get_ref().id;
+        self.with_store(|store| store.cancel(id))
+            .map_err(error::to_status)?;
+        info!("Cancelled scheduled deploy {id}");
+
+        Ok(Response::new(Empty {}))
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+use crate::{
+    error,
+    proto::{secrets_server::Secrets, Empty, SecretList, SecretName, SetSecretRequest},
+    secret::SecretStore,
+};
+
+pub struct SecretsService {
+    secret_store: Arc<Mutex<SecretStore>>,
+}
+
+impl SecretsService {
+    pub fn new(secret_store: Arc<Mutex<SecretStore>>) -> Self {
+        Self { secret_store }
+    }
+
+    fn with_store<T>(
+        &self,
+        f: impl FnOnce(&SecretStore) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let store = self
+            .secret_store
+            .lock()
+            .map_err(|err| anyhow!("secret store lock error: {err}"))?;
+
+        f(&store).inspect_err(|err| error!("secret store error: {err:?}"))
+    }
+}
+
+#[tonic::async_trait]
+impl Secrets for SecretsService {
+    async fn set_secret(
+        &self,
+        request: Request<SetSecretRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let req = request.get_ref();
+        self.with_store(|store| store.set(&req.name, &req.value))
+            .map_err(error::to_status)?;
+        info!("Stored secret {}", req.name);
+
+        Ok(Response::new(Empty {}))
+    }
+
+    async fn list_secrets(&self, _: Request<Empty>) -> Result<Response<SecretList>, Status> {
+        let names = self
+            .with_store(|store| store.names())
+            .map_err(error::to_status)?;
+        Ok(Response::new(SecretList { names }))
+    }
+
+    async fn delete_secret(&self, request: Request<SecretName>) -> Result<Response<Empty>, Status> {
+        let name = &request.get_ref().name;
+        self.with_store(|store| store.delete(name))
+            .map_err(error::to_status)?;
+        info!("Deleted secret {name}");
+
+        Ok(Response::new(Empty {}))
+    }
+}
@@ -8,48 +8,108 @@ use tokio_stream::{wrappers::ReceiverStream, Stream};
 use uuid::Uuid;
 
 use crate::{
-    proto::{system_server::System, Empty, InfoReply, LogReply},
+    proto::{system_server::System, Empty, HistoryReply, HistoryRequest, InfoReply, LogReply},
     subscriber::relay::{LogRelay, LogSender},
 };
 use tonic::{Request, Response, Status};
 
 use anyhow::Result;
-use tracing::{debug, error, info};
+use chrono::Utc;
+use tracing::{debug, error, info, warn};
 
 use crate::config::Config;
+use crate::docker::DockerClient;
+use crate::info::containers::ContainerCache;
+use crate::info::history::History;
+use crate::info::metrics::{MetricsProvider, SysinfoMetrics};
 
 pub struct SystemService {
     info_reply: Arc<Mutex<InfoReply>>,
+    history: Arc<Mutex<History>>,
     log_relay: Arc<Mutex<LogRelay>>,
 }
 
 impl SystemService {
-    pub fn new(config: Config, log_relay: Arc<Mutex<LogRelay>>) -> Self {
+    pub fn new(config: Config, docker: DockerClient, log_relay: Arc<Mutex<LogRelay>>) -> Self {
         let info_reply = Arc::new(Mutex::new(InfoReply::default()));
         let update_interval = Duration::from_millis(config.update_interval);
 
+        let history = match &config.history_path {
+            Some(path) => {
+                History::open(config.history_length, path.clone()).unwrap_or_else(|err| {
+                    warn!("Starting with an empty history: {err:?}");
+                    let mut history = History::new(config.history_length);
+                    history.set_path(path.clone());
+                    history
+                })
+            }
+            None => History::new(config.history_length),
+        };
+        let history = Arc::new(Mutex::new(history));
+
         let i = Arc::clone(&info_reply);
+        let h = Arc::clone(&history);
         tokio::task::spawn(async move {
-            run_updater(update_interval, i).await;
+            run_updater(update_interval, docker, SysinfoMetrics::new(), i, h).await;
         });
 
         Self {
             info_reply,
+            history,
             log_relay,
         }
     }
 }
 
-async fn run_updater(update_interval: Duration, info: Arc<Mutex<InfoReply>>) {
+async fn run_updater(
+    update_interval: Duration,
+    docker: DockerClient,
+    mut metrics: impl MetricsProvider,
+    info: Arc<Mutex<InfoReply>>,
+    history: Arc<Mutex<History>>,
+) {
     info!("Starting info updater with interval {:?}", update_interval);
+    let mut containers = ContainerCache::default();
     loop {
         tokio::time::sleep(update_interval).await;
-        match crate::info::info().await {
-            Ok(i) => match info.lock() {
-                Ok(mut info) => *info = i,
-                Err(err) => error!("{err:?}"),
-            },
-            Err(err) => error!("update error {err:?}"),
+        let host = match crate::info::sample(metrics).await {
+            Ok((provider, host)) => {
+                metrics = provider;
+                host
+            }
+            Err(err) => {
+                error!("metrics sampling failed, stopping updater: {err:?}");
+                return;
+            }
+        };
+
+        let mut reply = crate::info::info(host, &docker, &mut containers).await;
+
+        let sample = crate::info::history::sample(&reply, Utc::now().timestamp_millis());
+        let snapshot = match history.lock() {
+            Ok(mut history) => history.record(sample),
+            Err(err) => {
+                error!("{err:?}");
+                None
+            }
+        };
+        if let Some(snapshot) = snapshot {
+            if let Err(err) = snapshot.save().await {
+                error!("Saving history failed: {err:?}");
+            }
+        }
+
+        match info.lock() {
+            Ok(mut info) => {
+                match (info.docker_error.is_empty(), reply.docker_error.is_empty()) {
+                    (true, false) => warn!("Docker unreachable: {}", reply.docker_error),
+                    (false, true) => info!("Docker reachable again"),
+                    _ => {}
+                }
+                crate::info::carry_over_docker_info(&mut reply, &mut info);
+                *info = reply;
+            }
+            Err(err) => error!("{err:?}"),
         }
     }
 }
@@ -91,6 +151,21 @@ impl System for SystemService {
         };
     }
 
+    async fn get_history(
+        &self,
+        request: Request<HistoryRequest>,
+    ) -> Result<Response<HistoryReply>, Status> {
+        match self.history.lock() {
+            Ok(history) => Ok(Response::new(HistoryReply {
+                samples: history.query(request.get_ref()),
+            })),
+            Err(err) => {
+                error!("{err:?}");
+                Err(Status::from_error("Lock is poisoned".into()))
+            }
+        }
+    }
+
     type LogStream = Pin<Box<dyn Stream<Item = Result<LogReply, Status>> + Send>>;
 
     async fn log(
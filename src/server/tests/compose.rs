@@ -0,0 +1,247 @@
+use tonic::{Code, Status};
+
+use crate::proto::{compose_client::ComposeClient, DeployRequest};
+use crate::secret::SecretStore;
+
+use super::Harness;
+
+const WEB: &str = r#"
+image = "nginx"
+tag = "1.27"
+container_name = "web"
+ports = []
+env = { GREETING = "hello" }
+"#;
+
+/// Runs a deploy or undeploy to the end, returning its output or the final error.
+async fn collect(
+    stream: Result<tonic::Response<tonic::Streaming<crate::proto::DeployReply>>, Status>,
+) -> Result<Vec<String>, Status> {
+    let mut stream = stream?.into_inner();
+    let mut output = Vec::new();
+    while let Some(reply) = stream.message().await? {
+        output.push(reply.output);
+    }
+    Ok(output)
+}
+
+async fn deploy(
+    client: &mut ComposeClient<tonic::transport::Channel>,
+    path: &str,
+) -> Result<Vec<String>, Status> {
+    collect(client.deploy(request(path)).await).await
+}
+
+fn request(path: &str) -> DeployRequest {
+    DeployRequest {
+        path: path.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn deploy_pulls_image_and_starts_container() {
+    let harness = Harness::start().await;
+    harness.write_definition("web.toml", WEB);
+    let mut client = harness.compose_client().await;
+
+    let output = deploy(&mut client, "web.toml").await.unwrap();
+
+    assert_eq!(output.last().unwrap(), "Deployed web");
+    let container = harness.docker.container("web").unwrap();
+    assert_eq!(container.image, "nginx:1.27");
+    assert_eq!(container.status, "running");
+    assert_eq!(container.env, vec!["GREETING=hello"]);
+}
+
+#[tokio::test]
+async fn redeploy_replaces_previous_container() {
+    let harness = Harness::start().await;
+    harness.write_definition("web.toml", WEB);
+    let mut client = harness.compose_client().await;
+
+    deploy(&mut client, "web.toml").await.unwrap();
+    let first = harness.docker.container("web").unwrap();
+    deploy(&mut client, "web.toml").await.unwrap();
+
+    let containers = harness.docker.containers();
+    assert_eq!(containers.len(), 1);
+    assert_eq!(containers[0].name, "web");
+    assert_eq!(containers[0].status, "running");
+    assert_ne!(containers[0].id, first.id);
+}
+
+#[tokio::test]
+async fn failed_redeploy_restores_previous_container() {
+    let harness = Harness::start().await;
+    harness.write_definition("web.toml", WEB);
+    let mut client = harness.compose_client().await;
+    deploy(&mut client, "web.toml").await.unwrap();
+    let previous = harness.docker.container("web").unwrap();
+
+    harness.docker.fail_start("nginx:broken");
+    harness.write_definition("web.toml", &WEB.replace("1.27", "broken"));
+    let status = deploy(&mut client, "web.toml").await.unwrap_err();
+
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("mock failure"));
+    let containers = harness.docker.containers();
+    assert_eq!(containers.len(), 1);
+    assert_eq!(containers[0].id, previous.id);
+    assert_eq!(containers[0].name, "web");
+    assert_eq!(containers[0].status, "running");
+}
+
+#[tokio::test]
+async fn failed_cleanup_keeps_the_deploy_error() {
+    let harness = Harness::start().await;
+    harness.docker.fail_start("nginx:broken");
+    harness.docker.fail_remove("nginx:broken");
+    harness.write_definition("web.toml", &WEB.replace("1.27", "broken"));
+    let mut client = harness.compose_client().await;
+
+    let status = deploy(&mut client, "web.toml").await.unwrap_err();
+
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("mock failure"));
+    assert!(!status.message().contains("mock removal failure"));
+}
+
+#[tokio::test]
+async fn redeploy_removes_leftover_previous_container() {
+    let harness = Harness::start().await;
+    harness.write_definition("web.toml", WEB);
+    let mut client = harness.compose_client().await;
+    deploy(&mut client, "web.toml").await.unwrap();
+    harness
+        .docker
+        .add_container("web-previous", "nginx:1.26", false);
+
+    deploy(&mut client, "web.toml").await.unwrap();
+
+    let containers = harness.docker.containers();
+    assert_eq!(containers.len(), 1);
+    assert_eq!(containers[0].name, "web");
+    assert_eq!(containers[0].status, "running");
+}
+
+#[tokio::test]
+async fn secret_files_get_configured_mode_and_owner() {
+    let harness = Harness::start().await;
+    let secrets = SecretStore::open(&harness.compose_dir.path().join(".secrets")).unwrap();
+    secrets.set("api_key", "hunter2").unwrap();
+    harness.write_definition(
+        "web.toml",
+        &format!(
+            r#"{WEB}
+            files = [
+                {{ source = "secret:api_key", target = "/run/secrets/shared" }},
+                {{ source = "secret:api_key", target = "/run/secrets/own", mode = 0o400, uid = 101, gid = 102 }},
+            ]
+            "#
+        ),
+    );
+    let mut client = harness.compose_client().await;
+
+    deploy(&mut client, "web.toml").await.unwrap();
+
+    let container = harness.docker.container("web").unwrap();
+    assert_eq!(container.archives.len(), 1);
+    let mut archive = tar::Archive::new(container.archives[0].as_slice());
+    let headers: Vec<_> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let header = entry.unwrap().header().clone();
+            (
+                header.path().unwrap().to_string_lossy().into_owned(),
+                header.mode().unwrap(),
+                header.uid().unwrap(),
+                header.gid().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        headers,
+        vec![
+            ("run/secrets/shared".to_owned(), 0o444, 0, 0),
+            ("run/secrets/own".to_owned(), 0o400, 101, 102),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn deploy_of_missing_image_reports_not_found() {
+    let harness = Harness::start().await;
+    harness.write_definition("web.toml", &WEB.replace("nginx", "missing/nginx"));
+    let mut client = harness.compose_client().await;
+
+    let status = deploy(&mut client, "web.toml").await.unwrap_err();
+
+    assert_eq!(status.code(), Code::NotFound);
+    assert!(harness.docker.containers().is_empty());
+}
+
+#[tokio::test]
+async fn stack_deploy_creates_networks_and_undeploy_removes_everything() {
+    let harness = Harness::start().await;
+    harness.write_definition(
+        "stack.toml",
+        r#"
+        networks = ["backend", "frontend"]
+
+        [services.db]
+        image = "postgres"
+        tag = "16"
+        container_name = "db"
+        ports = []
+
+        [services.app]
+        image = "app"
+        tag = "1"
+        container_name = "app"
+        ports = []
+        depends_on = ["db"]
+        "#,
+    );
+    let mut client = harness.compose_client().await;
+
+    deploy(&mut client, "stack.toml").await.unwrap();
+    assert!(harness.docker.has_network("backend"));
+    assert!(harness.docker.has_network("frontend"));
+    for name in ["db", "app"] {
+        let container = harness.docker.container(name).unwrap();
+        assert_eq!(container.status, "running");
+        assert_eq!(container.networks, vec!["backend", "frontend"]);
+    }
+
+    collect(client.undeploy(request("stack.toml")).await)
+        .await
+        .unwrap();
+    assert!(harness.docker.containers().is_empty());
+    assert!(!harness.docker.has_network("backend"));
+    assert!(!harness.docker.has_network("frontend"));
+}
+
+#[tokio::test]
+async fn failed_stack_deploy_reports_the_whole_error_chain() {
+    let harness = Harness::start().await;
+    harness.write_definition(
+        "stack.toml",
+        r#"
+        [services.db]
+        image = "postgres"
+        tag = "broken"
+        container_name = "db"
+        ports = []
+        "#,
+    );
+    harness.docker.fail_start("postgres:broken");
+    let mut client = harness.compose_client().await;
+
+    let status = deploy(&mut client, "stack.toml").await.unwrap_err();
+
+    assert_eq!(status.code(), Code::Internal);
+    assert!(status.message().contains("failed to deploy service 'db'"));
+    assert!(status.message().contains("mock failure"));
+}
@@ -0,0 +1,242 @@
+use tonic::Code;
+
+use crate::proto::{
+    upload_chunk, ArchiveRequest, ContainerIdentifier, KillContainerRequest,
+    RemoveContainerRequest, RenameContainerRequest, StopContainerRequest, UploadChunk,
+};
+
+use super::{
+    mock_docker::{MockDocker, EXPORT_SIZE},
+    Harness,
+};
+
+fn id(id: &str) -> ContainerIdentifier {
+    ContainerIdentifier { id: id.to_owned() }
+}
+
+#[tokio::test]
+async fn container_actions_follow_lifecycle() {
+    let harness = Harness::start().await;
+    let container = harness.docker.add_container("web", "nginx:1.27", false);
+    let mut client = harness.docker_client().await;
+    let status = |harness: &Harness| harness.docker.container(&container).unwrap().status;
+
+    client.start_container(id(&container)).await.unwrap();
+    assert_eq!(status(&harness), "running");
+
+    client.pause_container(id(&container)).await.unwrap();
+    assert_eq!(status(&harness), "paused");
+
+    client.unpause_container(id(&container)).await.unwrap();
+    assert_eq!(status(&harness), "running");
+
+    client
+        .stop_container(StopContainerRequest {
+            id: container.clone(),
+            timeout: Some(1),
+        })
+        .await
+        .unwrap();
+    assert_eq!(status(&harness), "exited");
+
+    client
+        .restart_container(StopContainerRequest {
+            id: container.clone(),
+            timeout: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(status(&harness), "running");
+
+    client
+        .kill_container(KillContainerRequest {
+            id: container.clone(),
+            signal: "SIGTERM".to_owned(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(status(&harness), "exited");
+
+    client
+        .rename_container(RenameContainerRequest {
+            id: container.clone(),
+            name: "web-old".to_owned(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        harness.docker.container(&container).unwrap().name,
+        "web-old"
+    );
+
+    client
+        .remove_container(RemoveContainerRequest {
+            id: container.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(harness.docker.containers().is_empty());
+}
+
+#[tokio::test]
+async fn redundant_start_and_stop_succeed() {
+    let harness = Harness::start().await;
+    let container = harness.docker.add_container("web", "nginx:1.27", true);
+    let mut client = harness.docker_client().await;
+
+    client.start_container(id(&container)).await.unwrap();
+    client
+        .stop_container(StopContainerRequest {
+            id: container.clone(),
+            timeout: None,
+        })
+        .await
+        .unwrap();
+    client
+        .stop_container(StopContainerRequest {
+            id: container.clone(),
+            timeout: None,
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn docker_errors_map_to_status_codes() {
+    let harness = Harness::start().await;
+    let running = harness.docker.add_container("web", "nginx:1.27", true);
+    harness.docker.add_container("db", "postgres:16", false);
+    let mut client = harness.docker_client().await;
+
+    let status = client.start_container(id("unknown")).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+    assert!(status.message().contains("No such container"));
+
+    let status = client
+        .remove_container(RemoveContainerRequest {
+            id: running.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let status = client
+        .rename_container(RenameContainerRequest {
+            id: running.clone(),
+            name: "db".to_owned(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let status = client.unpause_container(id(&running)).await.unwrap_err();
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    client
+        .remove_container(RemoveContainerRequest {
+            id: running,
+            force: true,
+            remove_volumes: false,
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn events_are_streamed() {
+    let harness = Harness::start().await;
+    let container = harness.docker.add_container("web", "nginx:1.27", false);
+    let mut client = harness.docker_client().await;
+
+    let mut events = client
+        .events(crate::proto::Empty {})
+        .await
+        .unwrap()
+        .into_inner();
+    client.start_container(id(&container)).await.unwrap();
+
+    let event = events.message().await.unwrap().unwrap();
+    assert_eq!(event.event_type, "container");
+    assert_eq!(event.action, "start");
+    assert_eq!(event.id, container);
+    assert_eq!(event.attributes["name"], "web");
+}
+
+#[tokio::test]
+async fn negotiates_older_api_version() {
+    let docker = MockDocker::with_api_version("1.41");
+    let mut client = docker.client();
+
+    client.negotiate_api_version().await.unwrap();
+    client.list_containers().await.unwrap();
+
+    assert_eq!(client.api_version(), "1.41");
+    assert_eq!(
+        docker.requests(),
+        vec!["GET /version", "GET /v1.41/containers/json?all=true"]
+    );
+}
+
+#[tokio::test]
+async fn caps_api_version_at_supported_one() {
+    let docker = MockDocker::with_api_version("1.99");
+    let mut client = docker.client();
+
+    client.negotiate_api_version().await.unwrap();
+
+    assert_eq!(client.api_version(), crate::docker::client::MAX_API_VERSION);
+}
+
+#[tokio::test]
+async fn export_is_not_limited_by_the_download_size() {
+    let harness = Harness::start().await;
+    let container = harness.docker.add_container("web", "nginx:1.27", false);
+    let mut client = harness.docker_client().await;
+
+    let mut stream = client.export(id(&container)).await.unwrap().into_inner();
+    let mut size = 0;
+    while let Some(chunk) = stream.message().await.unwrap() {
+        size += chunk.data.len();
+    }
+
+    assert_eq!(size, EXPORT_SIZE);
+}
+
+fn upload(id: &str, chunks: &[&[u8]]) -> impl tokio_stream::Stream<Item = UploadChunk> {
+    let target = UploadChunk {
+        chunk: Some(upload_chunk::Chunk::Target(ArchiveRequest {
+            id: id.to_owned(),
+            path: "/tmp".to_owned(),
+        })),
+    };
+    let data = chunks.iter().map(|data| UploadChunk {
+        chunk: Some(upload_chunk::Chunk::Data(data.to_vec())),
+    });
+    tokio_stream::iter(std::iter::once(target).chain(data).collect::<Vec<_>>())
+}
+
+#[tokio::test]
+async fn uploads_are_streamed_within_the_limit() {
+    let harness = Harness::start().await;
+    let container = harness.docker.add_container("web", "nginx:1.27", true);
+    let mut client = harness.docker_client().await;
+
+    client
+        .upload_archive(upload(&container, &[&[1; 512], &[2; 512]]))
+        .await
+        .unwrap();
+
+    let archives = harness.docker.container(&container).unwrap().archives;
+    assert_eq!(archives, vec![[[1; 512], [2; 512]].concat()]);
+
+    let status = client
+        .upload_archive(upload(&container, &[&[1; 512], &[2; 512], &[3; 1]]))
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::ResourceExhausted);
+    let archives = harness.docker.container(&container).unwrap().archives;
+    assert_eq!(archives.len(), 1);
+}
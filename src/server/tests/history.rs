@@ -0,0 +1,210 @@
+use crate::info::history::{self, History};
+use crate::proto::{
+    ContainerSample, ContainerStats, Cpu, CpuInfo, Disk, DiskInfo, DockerInfo, HistoryRequest,
+    HistorySample, InfoReply, MemoryInfo, Network, NetworkInfo,
+};
+
+fn at(timestamp: i64, cpu_usage: f32, memory_used: u64) -> HistorySample {
+    HistorySample {
+        timestamp,
+        cpu_usage: vec![cpu_usage, cpu_usage * 2.0],
+        memory_used,
+        memory_total: 4096,
+        ..Default::default()
+    }
+}
+
+fn container(id: &str, cpu_usage: f32) -> ContainerSample {
+    ContainerSample {
+        id: id.to_owned(),
+        name: format!("/{id}"),
+        cpu_usage,
+        memory_usage: 100,
+    }
+}
+
+fn timestamps(samples: &[HistorySample]) -> Vec<i64> {
+    samples.iter().map(|s| s.timestamp).collect()
+}
+
+#[test]
+fn oldest_samples_are_dropped_when_full() {
+    let mut history = History::new(3);
+    for t in 1..=5 {
+        history.push(at(t, 0.0, 0));
+    }
+
+    let samples = history.query(&HistoryRequest::default());
+
+    assert_eq!(timestamps(&samples), vec![3, 4, 5]);
+}
+
+#[test]
+fn query_filters_time_range() {
+    let mut history = History::new(10);
+    for t in 1..=6 {
+        history.push(at(t * 1000, 0.0, 0));
+    }
+
+    let samples = history.query(&HistoryRequest {
+        from: 2000,
+        to: 4000,
+        max_points: 0,
+    });
+    assert_eq!(timestamps(&samples), vec![2000, 3000, 4000]);
+
+    let samples = history.query(&HistoryRequest {
+        from: 5000,
+        ..Default::default()
+    });
+    assert_eq!(timestamps(&samples), vec![5000, 6000]);
+}
+
+#[test]
+fn query_averages_down_to_max_points() {
+    let mut history = History::new(10);
+    history.push(at(1, 10.0, 1000));
+    history.push(HistorySample {
+        containers: vec![container("web", 20.0)],
+        ..at(2, 30.0, 2000)
+    });
+    history.push(HistorySample {
+        containers: vec![container("web", 40.0), container("db", 5.0)],
+        ..at(3, 50.0, 3000)
+    });
+    history.push(at(4, 70.0, 4000));
+    history.push(at(5, 90.0, 5000));
+
+    let samples = history.query(&HistoryRequest {
+        max_points: 2,
+        ..Default::default()
+    });
+
+    assert_eq!(timestamps(&samples), vec![3, 5]);
+    assert_eq!(samples[0].cpu_usage, vec![30.0, 60.0]);
+    assert_eq!(samples[0].memory_used, 2000);
+    assert_eq!(samples[0].memory_total, 4096);
+    assert_eq!(samples[0].containers.len(), 2);
+    assert_eq!(samples[0].containers[0].id, "web");
+    assert_eq!(samples[0].containers[0].cpu_usage, 30.0);
+    assert_eq!(samples[0].containers[1].cpu_usage, 5.0);
+    assert_eq!(samples[1].cpu_usage, vec![80.0, 160.0]);
+    assert!(samples[1].containers.is_empty());
+}
+
+#[tokio::test]
+async fn persisted_history_survives_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history");
+
+    let mut history = History::open(100, path.clone()).unwrap();
+    for t in 1..=59 {
+        assert!(history.record(at(t, 0.0, 0)).is_none());
+    }
+    history
+        .record(at(60, 0.0, 0))
+        .unwrap()
+        .save()
+        .await
+        .unwrap();
+    assert!(history.record(at(61, 0.0, 0)).is_none());
+
+    let history = History::open(10, path).unwrap();
+    let samples = history.query(&HistoryRequest::default());
+    assert_eq!(timestamps(&samples), (51..=60).collect::<Vec<_>>());
+}
+
+#[test]
+fn corrupt_history_file_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history");
+    std::fs::write(&path, b"not a history").unwrap();
+
+    assert!(History::open(10, path).is_err());
+}
+
+#[test]
+fn sample_condenses_info_reply() {
+    let reply = InfoReply {
+        memory_info: Some(MemoryInfo {
+            total: 4096,
+            used: 1024,
+            swap_total: 512,
+            swap_used: 128,
+            ..Default::default()
+        }),
+        disk_info: Some(DiskInfo {
+            disks: vec![
+                Disk {
+                    total_space: 100,
+                    available_space: 40,
+                    ..Default::default()
+                },
+                Disk {
+                    total_space: 50,
+                    available_space: 50,
+                    ..Default::default()
+                },
+            ],
+        }),
+        cpu_info: Some(CpuInfo {
+            cpus: vec![
+                Cpu {
+                    cpu_usage: 10.0,
+                    ..Default::default()
+                },
+                Cpu {
+                    cpu_usage: 20.0,
+                    ..Default::default()
+                },
+            ],
+        }),
+        network_info: Some(NetworkInfo {
+            networks: vec![
+                Network {
+                    name: "eth0".to_owned(),
+                    received: 300,
+                    transmitted: 30,
+                },
+                Network {
+                    name: "wlan0".to_owned(),
+                    received: 200,
+                    transmitted: 20,
+                },
+            ],
+        }),
+        docker_info: Some(DockerInfo {
+            container_list: vec![
+                crate::proto::Container {
+                    id: "web".to_owned(),
+                    names: vec!["/web".to_owned()],
+                    stats: Some(ContainerStats {
+                        cpu_usage: 12.5,
+                        memory_usage: 64,
+                        memory_limit: 1024,
+                    }),
+                    ..Default::default()
+                },
+                crate::proto::Container {
+                    id: "stopped".to_owned(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let sample = history::sample(&reply, 42);
+
+    assert_eq!(sample.timestamp, 42);
+    assert_eq!(sample.cpu_usage, vec![10.0, 20.0]);
+    assert_eq!((sample.memory_used, sample.memory_total), (1024, 4096));
+    assert_eq!((sample.swap_used, sample.swap_total), (128, 512));
+    assert_eq!((sample.disk_used, sample.disk_total), (60, 150));
+    assert_eq!(sample.network_received, 500);
+    assert_eq!(sample.network_transmitted, 50);
+    assert_eq!(sample.containers.len(), 1);
+    assert_eq!(sample.containers[0].name, "/web");
+    assert_eq!(sample.containers[0].cpu_usage, 12.5);
+}
@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+
+use crate::info::containers::ContainerCache;
+use crate::info::metrics::{HostMetrics, MetricsProvider, SysinfoMetrics};
+use crate::proto::{Cpu, CpuInfo, Disk, DiskInfo, EngineKind, MemoryInfo, Network, NetworkInfo};
+
+use crate::proto::{ContainerIdentifier, StopContainerRequest};
+
+use super::{mock_docker::MockDocker, Harness};
+
+/// Plays back prepared samples, repeating the last one once the script runs out.
+struct ScriptedMetrics {
+    samples: VecDeque<HostMetrics>,
+}
+
+impl ScriptedMetrics {
+    fn new(samples: impl IntoIterator<Item = HostMetrics>) -> Self {
+        Self {
+            samples: samples.into_iter().collect(),
+        }
+    }
+}
+
+impl MetricsProvider for ScriptedMetrics {
+    fn sample(&mut self) -> HostMetrics {
+        match self.samples.len() {
+            0 => HostMetrics::default(),
+            1 => self.samples[0].clone(),
+            _ => self.samples.pop_front().unwrap(),
+        }
+    }
+}
+
+fn host(cpu_usage: f32, used_memory: u64) -> HostMetrics {
+    HostMetrics {
+        name: "TestOS".to_owned(),
+        kernel_version: "6.1.0".to_owned(),
+        os_version: "12".to_owned(),
+        host_name: "pi".to_owned(),
+        memory_info: MemoryInfo {
+            total: 4096,
+            free: 4096 - used_memory,
+            available: 4096 - used_memory,
+            used: used_memory,
+            swap_total: 1024,
+            swap_used: 0,
+        },
+        disk_info: DiskInfo {
+            disks: vec![Disk {
+                name: "/dev/mmcblk0p2".to_owned(),
+                kind: "SSD".to_owned(),
+                file_system: "ext4".to_owned(),
+                total_space: 64,
+                available_space: 32,
+            }],
+        },
+        cpu_info: CpuInfo {
+            cpus: vec![
+                Cpu {
+                    name: "cpu0".to_owned(),
+                    cpu_usage,
+                    frequency: 1800,
+                },
+                Cpu {
+                    name: "cpu1".to_owned(),
+                    cpu_usage,
+                    frequency: 1800,
+                },
+            ],
+        },
+        network_info: NetworkInfo {
+            networks: vec![Network {
+                name: "eth0".to_owned(),
+                received: 2048,
+                transmitted: 512,
+            }],
+        },
+    }
+}
+
+#[tokio::test]
+async fn info_reply_combines_host_metrics_and_docker_info() {
+    let docker = MockDocker::start();
+    let id = docker.add_container("web", "nginx:1.27", true);
+    let (_, sample) = crate::info::sample(ScriptedMetrics::new([host(12.5, 1024)]))
+        .await
+        .unwrap();
+
+    let reply = crate::info::info(sample, &docker.client(), &mut ContainerCache::default()).await;
+
+    assert_eq!(reply.name, "TestOS");
+    assert_eq!(reply.kernel_version, "6.1.0");
+    assert_eq!(reply.os_version, "12");
+    assert_eq!(reply.host_name, "pi");
+    assert_eq!(reply.memory_info.unwrap().used, 1024);
+    assert_eq!(reply.disk_info.unwrap().disks[0].file_system, "ext4");
+    let cpus = reply.cpu_info.unwrap().cpus;
+    assert_eq!(cpus.len(), 2);
+    assert_eq!(cpus[0].cpu_usage, 12.5);
+    assert_eq!(reply.network_info.unwrap().networks[0].received, 2048);
+
+    let docker_info = reply.docker_info.unwrap();
+    assert_eq!(docker_info.engine(), EngineKind::Docker);
+    let version = docker_info.version.unwrap();
+    assert_eq!(version.version, "27.0.0-mock");
+    assert_eq!(version.negotiated_api_version, "1.47");
+    assert_eq!(docker_info.container_list.len(), 1);
+    let container = &docker_info.container_list[0];
+    assert_eq!(container.id, id);
+    assert_eq!(container.names, vec!["/web"]);
+    assert_eq!(container.state, "running");
+    assert_eq!(container.logs.len(), 1);
+    assert!(container.logs[0].ends_with("output of web"));
+}
+
+#[tokio::test]
+async fn provider_is_handed_back_for_the_next_sample() {
+    let metrics = ScriptedMetrics::new([host(10.0, 1000), host(20.0, 2000)]);
+
+    let (metrics, first) = crate::info::sample(metrics).await.unwrap();
+    let (metrics, second) = crate::info::sample(metrics).await.unwrap();
+    let (_, third) = crate::info::sample(metrics).await.unwrap();
+
+    assert_eq!(first.memory_info.used, 1000);
+    assert_eq!(second.memory_info.used, 2000);
+    assert_eq!(third.cpu_info.cpus[0].cpu_usage, 20.0);
+}
+
+#[tokio::test]
+async fn unreachable_docker_keeps_host_metrics() {
+    let docker = MockDocker::start();
+    let client = docker.client();
+    drop(docker);
+
+    let reply = crate::info::info(host(5.0, 512), &client, &mut ContainerCache::default()).await;
+
+    assert_eq!(reply.host_name, "pi");
+    assert_eq!(reply.memory_info.unwrap().used, 512);
+    assert!(reply.docker_info.is_none());
+    assert!(reply.docker_error.contains("docker unavailable"));
+    assert_eq!(reply.docker_updated_at, 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn unresponsive_docker_times_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("docker.sock");
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    // Accepts connections and never answers.
+    let server = tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(stream);
+        }
+    });
+    let client =
+        crate::docker::DockerClient::new(crate::docker::Endpoint::Unix(socket), None).unwrap();
+
+    let reply = crate::info::info(host(5.0, 512), &client, &mut ContainerCache::default()).await;
+
+    assert_eq!(reply.memory_info.unwrap().used, 512);
+    assert!(reply.docker_info.is_none());
+    assert!(reply.docker_error.contains("timed out"));
+    server.abort();
+}
+
+#[tokio::test]
+async fn failed_docker_query_keeps_last_docker_info() {
+    let docker = MockDocker::start();
+    docker.add_container("web", "nginx:1.27", true);
+    let client = docker.client();
+    let mut containers = ContainerCache::default();
+    let mut previous = crate::info::info(host(1.0, 100), &client, &mut containers).await;
+    assert!(previous.docker_error.is_empty());
+    assert!(previous.docker_updated_at > 0);
+    let updated_at = previous.docker_updated_at;
+    drop(docker);
+
+    let mut reply = crate::info::info(host(2.0, 200), &client, &mut containers).await;
+    crate::info::carry_over_docker_info(&mut reply, &mut previous);
+
+    assert_eq!(reply.memory_info.unwrap().used, 200);
+    assert!(!reply.docker_error.is_empty());
+    assert_eq!(reply.docker_updated_at, updated_at);
+    assert_eq!(reply.docker_info.unwrap().container_list.len(), 1);
+}
+
+fn log_requests(docker: &MockDocker) -> Vec<String> {
+    docker
+        .requests()
+        .into_iter()
+        .filter(|request| request.contains("/logs"))
+        .collect()
+}
+
+#[tokio::test]
+async fn logs_of_unchanged_stopped_containers_are_fetched_once() {
+    let docker = MockDocker::start();
+    docker.add_container("web", "nginx:1.27", false);
+    docker.add_container("db", "postgres:16", false);
+    let client = docker.client();
+    let mut containers = ContainerCache::default();
+
+    for _ in 0..3 {
+        let docker_info = containers.docker_info(&client).await.unwrap();
+        assert_eq!(docker_info.container_list.len(), 2);
+        assert!(docker_info.container_list.iter().all(|c| c.logs.len() == 1));
+    }
+
+    assert_eq!(log_requests(&docker).len(), 2);
+}
+
+#[tokio::test]
+async fn running_containers_only_fetch_new_log_lines() {
+    let docker = MockDocker::start();
+    let id = docker.add_container("web", "nginx:1.27", true);
+    let client = docker.client();
+    let mut containers = ContainerCache::default();
+
+    containers.docker_info(&client).await.unwrap();
+    docker.push_log(&id, "second line");
+    docker.push_log(&id, "third line");
+    let docker_info = containers.docker_info(&client).await.unwrap();
+
+    let logs = &docker_info.container_list[0].logs;
+    assert_eq!(logs.len(), 3);
+    assert!(logs[0].ends_with("output of web"));
+    assert!(logs[2].ends_with("third line"));
+    let requests = log_requests(&docker);
+    assert!(requests[0].contains("tail=1000"));
+    assert!(requests[1].contains("since=1704067200.000000000"));
+}
+
+#[tokio::test]
+async fn state_change_refetches_container() {
+    let harness = Harness::start().await;
+    let id = harness.docker.add_container("web", "nginx:1.27", false);
+    let client = harness.docker.client();
+    let mut containers = ContainerCache::default();
+    containers.docker_info(&client).await.unwrap();
+    containers.docker_info(&client).await.unwrap();
+    assert_eq!(log_requests(&harness.docker).len(), 1);
+
+    let mut service = harness.docker_client().await;
+    service
+        .start_container(ContainerIdentifier { id: id.clone() })
+        .await
+        .unwrap();
+    harness.docker.push_log(&id, "started");
+    service
+        .stop_container(StopContainerRequest { id, timeout: None })
+        .await
+        .unwrap();
+    let docker_info = containers.docker_info(&client).await.unwrap();
+
+    assert_eq!(docker_info.container_list[0].state, "exited");
+    assert_eq!(docker_info.container_list[0].logs.len(), 2);
+    let requests = log_requests(&harness.docker);
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].contains("tail=1000"));
+}
+
+#[tokio::test]
+async fn running_containers_report_stats() {
+    let docker = MockDocker::start();
+    docker.add_container("web", "nginx:1.27", true);
+    docker.add_container("db", "postgres:16", false);
+    let client = docker.client();
+    let mut containers = ContainerCache::default();
+
+    let first = containers.docker_info(&client).await.unwrap();
+    let second = containers.docker_info(&client).await.unwrap();
+
+    let stats = first.container_list[0].stats.unwrap();
+    assert_eq!(stats.cpu_usage, 0.0);
+    assert_eq!(stats.memory_usage, 64 << 20);
+    assert_eq!(stats.memory_limit, 1 << 30);
+    assert_eq!(second.container_list[0].stats.unwrap().cpu_usage, 50.0);
+    assert!(second.container_list[1].stats.is_none());
+}
+
+#[tokio::test]
+async fn sysinfo_provider_reports_host() {
+    let (_, sample) = crate::info::sample(SysinfoMetrics::new()).await.unwrap();
+
+    assert!(sample.memory_info.total > 0);
+    assert!(!sample.cpu_info.cpus.is_empty());
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use std::time::Duration;
+
+use chrono::Utc;
+use tonic::Code;
+
+use crate::job::{self, Definitions, Job, JobDefinition, JobRun, JOBS_DIR};
+use crate::proto::{self, JobIdentifier};
+
+use super::Harness;
+
+fn definition(schedule: &str) -> JobDefinition {
+    JobDefinition {
+        image: "alpine".to_owned(),
+        tag: "3".to_owned(),
+        command: vec!["true".to_owned()],
+        binds: None,
+        env: None,
+        schedule: schedule.to_owned(),
+        timeout: 60,
+    }
+}
+
+fn synced(definitions: Definitions, jobs: &mut HashMap<String, Job>) {
+    job::sync(jobs, definitions, Utc::now());
+}
+
+#[test]
+fn unparsable_definition_keeps_job_and_runs() {
+    let mut jobs = HashMap::new();
+    synced(
+        HashMap::from([("backup".to_owned(), Ok(definition("0 3 * * *")))]),
+        &mut jobs,
+    );
+    jobs.get_mut("backup").unwrap().record(JobRun {
+        started: 1,
+        finished: 2,
+        exit_code: 0,
+        logs: Vec::new(),
+    });
+
+    synced(
+        HashMap::from([(
+            "backup".to_owned(),
+            Err("invalid job definition".to_owned()),
+        )]),
+        &mut jobs,
+    );
+
+    let job = &jobs["backup"];
+    assert_eq!(job.definition.schedule, "0 3 * * *");
+    assert_eq!(job.runs.len(), 1);
+    assert_eq!(job.error.as_deref(), Some("invalid job definition"));
+    assert_eq!(
+        job.to_proto("backup").definition_error,
+        "invalid job definition"
+    );
+
+    synced(
+        HashMap::from([("backup".to_owned(), Ok(definition("0 4 * * *")))]),
+        &mut jobs,
+    );
+
+    let job = &jobs["backup"];
+    assert_eq!(job.definition.schedule, "0 4 * * *");
+    assert_eq!(job.runs.len(), 1);
+    assert!(job.error.is_none());
+}
+
+#[test]
+fn removed_and_new_invalid_definitions_are_not_kept() {
+    let mut jobs = HashMap::new();
+    synced(
+        HashMap::from([("backup".to_owned(), Ok(definition("0 3 * * *")))]),
+        &mut jobs,
+    );
+
+    synced(
+        HashMap::from([(
+            "cleanup".to_owned(),
+            Err("invalid job definition".to_owned()),
+        )]),
+        &mut jobs,
+    );
+
+    assert!(jobs.is_empty());
+}
+
+fn job_id(name: &str) -> JobIdentifier {
+    JobIdentifier {
+        name: name.to_owned(),
+    }
+}
+
+/// Polls the job list until `done` holds for the named job.
+async fn wait_for_job(harness: &Harness, name: &str, done: impl Fn(&proto::Job) -> bool) {
+    let mut client = harness.jobs_client().await;
+    for _ in 0..100 {
+        let jobs = client
+            .list_jobs(proto::Empty {})
+            .await
+            .unwrap()
+            .into_inner();
+        if jobs.jobs.iter().any(|job| job.name == name && done(job)) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("job {name} did not get there in time");
+}
+
+#[tokio::test]
+async fn hanging_job_is_stopped_after_its_timeout() {
+    let harness = Harness::start().await;
+    std::fs::create_dir(harness.compose_dir.path().join(JOBS_DIR)).unwrap();
+    harness.write_definition(
+        "jobs/backup.toml",
+        r#"
+        image = "alpine"
+        tag = "3"
+        command = ["sleep", "infinity"]
+        schedule = "0 3 * * *"
+        timeout = 1
+        "#,
+    );
+    harness.docker.hang_wait("alpine:3");
+    let mut client = harness.jobs_client().await;
+
+    let status = client.run_job(job_id("missing")).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    wait_for_job(&harness, "backup", |_| true).await;
+    client.run_job(job_id("backup")).await.unwrap();
+    let status = client.run_job(job_id("backup")).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    wait_for_job(&harness, "backup", |job| !job.running).await;
+    let jobs = client
+        .list_jobs(proto::Empty {})
+        .await
+        .unwrap()
+        .into_inner();
+    let run = &jobs.jobs[0].runs[0];
+    assert_eq!(run.exit_code, -1);
+    assert!(run.logs[0].contains("timed out"));
+    assert!(harness.docker.containers().is_empty());
+}
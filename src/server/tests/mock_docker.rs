@@ -0,0 +1,633 @@
+//! An in-process fake of the Docker Engine API served on a temporary unix socket.
+//! It keeps containers, images, networks and volumes in memory and follows the
+//! daemon's status codes for conflicting or redundant requests.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use http_body_util::{combinators::BoxBody, BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::{server::conn::http1, service::service_fn, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use percent_encoding::percent_decode_str;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::net::UnixListener;
+use tokio::sync::broadcast;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+
+use crate::docker::{DockerClient, Endpoint};
+
+type Body = BoxBody<Bytes, Infallible>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockContainer {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub status: &'static str,
+    pub exit_code: i64,
+    pub env: Vec<String>,
+    pub logs: Vec<String>,
+    pub stats_samples: u64,
+    /// Tar archives uploaded into the container.
+    pub archives: Vec<Vec<u8>>,
+    pub networks: Vec<String>,
+}
+
+impl MockContainer {
+    fn is_running(&self) -> bool {
+        self.status == "running" || self.status == "paused"
+    }
+}
+
+/// 2024-01-01T00:00:00Z, the timestamp of the first log line.
+const LOG_EPOCH: i64 = 1_704_067_200;
+
+/// Bytes in a container export, larger than the harness download limit.
+pub const EXPORT_SIZE: usize = 4096;
+
+struct State {
+    api_version: String,
+    containers: Vec<MockContainer>,
+    images: HashSet<String>,
+    failing_images: HashSet<String>,
+    undeletable_images: HashSet<String>,
+    hanging_images: HashSet<String>,
+    networks: HashSet<String>,
+    volumes: HashSet<String>,
+    requests: Vec<String>,
+    events: broadcast::Sender<String>,
+    next_id: u64,
+}
+
+impl State {
+    fn find(&self, id_or_name: &str) -> Option<usize> {
+        self.containers
+            .iter()
+            .position(|c| c.id == id_or_name || c.name == id_or_name)
+    }
+
+    fn create(&mut self, name: &str, image: &str, env: Vec<String>) -> String {
+        self.next_id += 1;
+        let id = format!("{:064x}", self.next_id);
+        self.containers.push(MockContainer {
+            id: id.clone(),
+            name: name.to_owned(),
+            image: image.to_owned(),
+            status: "created",
+            exit_code: 0,
+            env,
+            logs: vec![format!("output of {name}")],
+            stats_samples: 0,
+            archives: Vec::new(),
+            networks: Vec::new(),
+        });
+        self.emit("create", self.containers.len() - 1);
+        id
+    }
+
+    fn emit(&self, action: &str, index: usize) {
+        let container = &self.containers[index];
+        let event = json!({
+            "Type": "container",
+            "Action": action,
+            "Actor": {
+                "ID": container.id,
+                "Attributes": { "name": container.name, "image": container.image },
+            },
+            "time": 0,
+        });
+        // Nobody listening is fine, events are only streamed while subscribed.
+        let _ = self.events.send(format!("{event}\n"));
+    }
+}
+
+/// A fake daemon, stopped when dropped.
+pub struct MockDocker {
+    state: Arc<Mutex<State>>,
+    socket: PathBuf,
+    server: JoinHandle<()>,
+    _dir: TempDir,
+}
+
+impl MockDocker {
+    pub fn start() -> Self {
+        Self::with_api_version("1.47")
+    }
+
+    /// Starts a daemon that reports `api_version` as the newest version it supports.
+    pub fn with_api_version(api_version: &str) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("docker.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let state = Arc::new(Mutex::new(State {
+            api_version: api_version.to_owned(),
+            containers: Vec::new(),
+            images: HashSet::new(),
+            failing_images: HashSet::new(),
+            undeletable_images: HashSet::new(),
+            hanging_images: HashSet::new(),
+            networks: HashSet::new(),
+            volumes: HashSet::new(),
+            requests: Vec::new(),
+            events: broadcast::channel(64).0,
+            next_id: 0,
+        }));
+
+        let server_state = state.clone();
+        // Connections live in the server task's set, so aborting it closes them too.
+        let server = tokio::spawn(async move {
+            let mut connections = JoinSet::new();
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                while connections.try_join_next().is_some() {}
+
+                let state = server_state.clone();
+                connections.spawn(async move {
+                    let service = service_fn(move |req| handle(state.clone(), req));
+                    let _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        Self {
+            state,
+            socket,
+            server,
+            _dir: dir,
+        }
+    }
+
+    pub fn client(&self) -> DockerClient {
+        DockerClient::new(Endpoint::Unix(self.socket.clone()), None).unwrap()
+    }
+
+    /// Adds a container, already running if `running` is set, and returns its id.
+    pub fn add_container(&self, name: &str, image: &str, running: bool) -> String {
+        let mut state = self.state.lock().unwrap();
+        state.images.insert(image.to_owned());
+        let id = state.create(name, image, Vec::new());
+        if running {
+            state.containers.last_mut().unwrap().status = "running";
+        }
+        id
+    }
+
+    /// Appends a line to the output of a container.
+    pub fn push_log(&self, id_or_name: &str, line: &str) {
+        let mut state = self.state.lock().unwrap();
+        let index = state.find(id_or_name).unwrap();
+        state.containers[index].logs.push(line.to_owned());
+    }
+
+    /// Makes every container of `image` fail to start.
+    pub fn fail_start(&self, image: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.images.insert(image.to_owned());
+        state.failing_images.insert(image.to_owned());
+    }
+
+    /// Makes removing any container of `image` fail.
+    pub fn fail_remove(&self, image: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.undeletable_images.insert(image.to_owned());
+    }
+
+    /// Makes waiting for any container of `image` never return.
+    pub fn hang_wait(&self, image: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.hanging_images.insert(image.to_owned());
+    }
+
+    pub fn container(&self, id_or_name: &str) -> Option<MockContainer> {
+        let state = self.state.lock().unwrap();
+        state.find(id_or_name).map(|i| state.containers[i].clone())
+    }
+
+    pub fn containers(&self) -> Vec<MockContainer> {
+        self.state.lock().unwrap().containers.clone()
+    }
+
+    pub fn has_network(&self, name: &str) -> bool {
+        self.state.lock().unwrap().networks.contains(name)
+    }
+
+    /// Every request as `METHOD /path?query`, including the API version prefix.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockDocker {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    req: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let request = format!("{method} {}", req.uri());
+    let query = parse_query(req.uri().query().unwrap_or_default());
+    let body = match req.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => return Ok(empty(StatusCode::BAD_REQUEST)),
+    };
+
+    let mut state = state.lock().unwrap();
+    state.requests.push(request);
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let segments = match segments.split_first() {
+        Some((version, rest)) if is_version(version) => rest.to_vec(),
+        _ => segments,
+    };
+
+    let response = match (&method, segments.as_slice()) {
+        (&Method::GET, ["version"]) => json_response(
+            StatusCode::OK,
+            json!({
+                "Version": "27.0.0-mock",
+                "ApiVersion": state.api_version,
+                "MinAPIVersion": "1.24",
+                "Components": [{ "Name": "Engine" }],
+            }),
+        ),
+        (&Method::GET, ["events"]) => events(&state),
+        (&Method::POST, ["images", "create"]) => pull(&mut state, &query),
+        (_, ["containers", rest @ ..]) => containers(&mut state, &method, rest, &query, &body),
+        (&Method::GET, ["networks", name]) => match state.networks.contains(*name) {
+            true => json_response(StatusCode::OK, json!({ "Name": name })),
+            false => error(StatusCode::NOT_FOUND, &format!("network {name} not found")),
+        },
+        (&Method::POST, ["networks", "create"]) => {
+            let name = json_field(&body, "Name");
+            state.networks.insert(name.clone());
+            json_response(StatusCode::CREATED, json!({ "Id": name }))
+        }
+        (&Method::POST, ["networks", name, "connect"]) => {
+            let container = json_field(&body, "Container");
+            match (state.networks.contains(*name), state.find(&container)) {
+                (true, Some(index)) => {
+                    state.containers[index].networks.push(name.to_string());
+                    empty(StatusCode::OK)
+                }
+                (false, _) => error(StatusCode::NOT_FOUND, &format!("network {name} not found")),
+                (true, None) => error(
+                    StatusCode::NOT_FOUND,
+                    &format!("No such container: {container}"),
+                ),
+            }
+        }
+        (&Method::DELETE, ["networks", name]) => match state.networks.remove(*name) {
+            true => empty(StatusCode::NO_CONTENT),
+            false => error(StatusCode::NOT_FOUND, &format!("network {name} not found")),
+        },
+        (&Method::POST, ["volumes", "create"]) => {
+            let name = json_field(&body, "Name");
+            state.volumes.insert(name.clone());
+            json_response(StatusCode::CREATED, json!({ "Name": name }))
+        }
+        _ => error(StatusCode::NOT_FOUND, "page not found"),
+    };
+
+    Ok(response)
+}
+
+fn containers(
+    state: &mut State,
+    method: &Method,
+    segments: &[&str],
+    query: &HashMap<String, String>,
+    body: &Bytes,
+) -> Response<Body> {
+    match (method, segments) {
+        (&Method::GET, ["json"]) => {
+            let list: Vec<Value> = state
+                .containers
+                .iter()
+                .map(|c| {
+                    json!({
+                        "Id": c.id,
+                        "Names": [format!("/{}", c.name)],
+                        "Image": c.image,
+                        "Command": "",
+                        "Created": 0,
+                        "Ports": [],
+                        "Status": c.status,
+                        "State": c.status,
+                    })
+                })
+                .collect();
+            json_response(StatusCode::OK, Value::Array(list))
+        }
+        (&Method::POST, ["create"]) => {
+            let name = query.get("name").cloned().unwrap_or_default();
+            let Ok(body) = serde_json::from_slice::<Value>(body) else {
+                return error(StatusCode::BAD_REQUEST, "invalid JSON");
+            };
+            let image = body["Image"].as_str().unwrap_or_default().to_owned();
+            let env = body["Env"]
+                .as_array()
+                .map(|env| {
+                    env.iter()
+                        .filter_map(|v| v.as_str().map(str::to_owned))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            if let Some(existing) = state.find(&name) {
+                let message = format!(
+                    "Conflict. The container name \"/{name}\" is already in use by container \"{}\"",
+                    state.containers[existing].id
+                );
+                return error(StatusCode::CONFLICT, &message);
+            }
+            if !state.images.contains(&image) {
+                return error(StatusCode::NOT_FOUND, &format!("No such image: {image}"));
+            }
+            // Like daemons before API 1.44, which only take one network at create.
+            let networks: Vec<String> = body["NetworkingConfig"]["EndpointsConfig"]
+                .as_object()
+                .map(|endpoints| endpoints.keys().cloned().collect())
+                .unwrap_or_default();
+            if networks.len() > 1 {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    "Container cannot be connected to network endpoints",
+                );
+            }
+
+            let id = state.create(&name, &image, env);
+            let index = state.containers.len() - 1;
+            state.containers[index].networks = networks;
+            json_response(StatusCode::CREATED, json!({ "Id": id, "Warnings": [] }))
+        }
+        (_, [id, rest @ ..]) => {
+            let Some(index) = state.find(id) else {
+                return error(StatusCode::NOT_FOUND, &format!("No such container: {id}"));
+            };
+            container(state, index, method, rest, query, body)
+        }
+        _ => error(StatusCode::NOT_FOUND, "page not found"),
+    }
+}
+
+fn container(
+    state: &mut State,
+    index: usize,
+    method: &Method,
+    segments: &[&str],
+    query: &HashMap<String, String>,
+    body: &Bytes,
+) -> Response<Body> {
+    let c = &state.containers[index];
+    let id = c.id.clone();
+    let flag = |name: &str| query.get(name).is_some_and(|v| v == "true" || v == "1");
+
+    let (status, exit_code, action) = match (method, segments) {
+        (&Method::GET, ["json"]) => {
+            return json_response(
+                StatusCode::OK,
+                json!({
+                    "Id": c.id,
+                    "Name": format!("/{}", c.name),
+                    "Image": c.image,
+                    "Config": { "Env": c.env },
+                    "State": {
+                        "Status": c.status,
+                        "Running": c.is_running(),
+                        "Paused": c.status == "paused",
+                        "ExitCode": c.exit_code,
+                        "Health": null,
+                    },
+                }),
+            );
+        }
+        (&Method::GET, ["logs"]) => {
+            // Line `i` is written `i` seconds after the epoch of the mock, `since` is inclusive.
+            let since = query
+                .get("since")
+                .and_then(|since| since.split('.').next()?.parse::<i64>().ok())
+                .map_or(0, |since| since - LOG_EPOCH);
+            let mut framed = Vec::new();
+            for (i, line) in c.logs.iter().enumerate().skip(since.max(0) as usize) {
+                let line = format!(
+                    "2024-01-01T00:{:02}:{:02}.000000000Z {line}\n",
+                    i / 60,
+                    i % 60
+                );
+                framed.extend_from_slice(&[1, 0, 0, 0]);
+                framed.extend_from_slice(&(line.len() as u32).to_be_bytes());
+                framed.extend_from_slice(line.as_bytes());
+            }
+            return full(StatusCode::OK, framed);
+        }
+        (&Method::GET, ["stats"]) => {
+            // Every sample adds half a second of CPU time on two cores out of two seconds
+            // of system time, which is 50%.
+            state.containers[index].stats_samples += 1;
+            let samples = state.containers[index].stats_samples;
+            return json_response(
+                StatusCode::OK,
+                json!({
+                    "cpu_stats": {
+                        "cpu_usage": { "total_usage": samples * 500_000_000 },
+                        "system_cpu_usage": samples * 2_000_000_000,
+                        "online_cpus": 2,
+                    },
+                    "memory_stats": {
+                        "usage": 96 << 20,
+                        "limit": 1 << 30,
+                        "stats": { "inactive_file": 32 << 20 },
+                    },
+                }),
+            );
+        }
+        (&Method::GET, ["export"]) => return full(StatusCode::OK, vec![0; EXPORT_SIZE]),
+        (&Method::PUT, ["archive"]) => {
+            state.containers[index].archives.push(body.to_vec());
+            return empty(StatusCode::OK);
+        }
+        (&Method::POST, ["wait"]) if state.hanging_images.contains(&c.image) => {
+            let stream = futures::stream::pending::<Result<Frame<Bytes>, Infallible>>();
+            return Response::new(BoxBody::new(StreamBody::new(stream)));
+        }
+        (&Method::POST, ["wait"]) => {
+            let exit_code = c.exit_code;
+            if c.is_running() {
+                state.containers[index].status = "exited";
+                state.emit("die", index);
+            }
+            return json_response(StatusCode::OK, json!({ "StatusCode": exit_code }));
+        }
+        (&Method::DELETE, []) => {
+            if c.is_running() && !flag("force") {
+                let message = format!(
+                    "You cannot remove a running container {id}. Stop the container before attempting removal or force remove"
+                );
+                return error(StatusCode::CONFLICT, &message);
+            }
+            if state.undeletable_images.contains(&c.image) {
+                return error(StatusCode::INTERNAL_SERVER_ERROR, "mock removal failure");
+            }
+            state.emit("destroy", index);
+            state.containers.remove(index);
+            return empty(StatusCode::NO_CONTENT);
+        }
+        (&Method::POST, ["rename"]) => {
+            let name = query.get("name").cloned().unwrap_or_default();
+            if state.find(&name).is_some_and(|other| other != index) {
+                return error(
+                    StatusCode::CONFLICT,
+                    &format!("Conflict. The container name \"/{name}\" is already in use"),
+                );
+            }
+            state.containers[index].name = name;
+            state.emit("rename", index);
+            return empty(StatusCode::NO_CONTENT);
+        }
+        (&Method::POST, ["start"]) if c.is_running() => return empty(StatusCode::NOT_MODIFIED),
+        (&Method::POST, ["start"]) if state.failing_images.contains(&c.image) => {
+            state.containers[index].status = "exited";
+            state.containers[index].exit_code = 128;
+            return error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to create task for container: mock failure",
+            );
+        }
+        (&Method::POST, ["start"]) => ("running", 0, "start"),
+        (&Method::POST, ["stop"]) if !c.is_running() => return empty(StatusCode::NOT_MODIFIED),
+        (&Method::POST, ["stop"]) => ("exited", 0, "stop"),
+        (&Method::POST, ["restart"]) => ("running", 0, "restart"),
+        (&Method::POST, ["pause"]) if c.status != "running" => {
+            return error(
+                StatusCode::CONFLICT,
+                &format!("Container {id} is not running"),
+            );
+        }
+        (&Method::POST, ["pause"]) => ("paused", 0, "pause"),
+        (&Method::POST, ["unpause"]) if c.status != "paused" => {
+            return error(
+                StatusCode::CONFLICT,
+                &format!("Container {id} is not paused"),
+            );
+        }
+        (&Method::POST, ["unpause"]) => ("running", 0, "unpause"),
+        (&Method::POST, ["kill"]) if !c.is_running() => {
+            return error(
+                StatusCode::CONFLICT,
+                &format!("Container {id} is not running"),
+            );
+        }
+        (&Method::POST, ["kill"]) => ("exited", 137, "kill"),
+        _ => return error(StatusCode::NOT_FOUND, "page not found"),
+    };
+
+    let container = &mut state.containers[index];
+    container.status = status;
+    container.exit_code = exit_code;
+    state.emit(action, index);
+    empty(StatusCode::NO_CONTENT)
+}
+
+fn pull(state: &mut State, query: &HashMap<String, String>) -> Response<Body> {
+    let image = query.get("fromImage").cloned().unwrap_or_default();
+    let tag = query.get("tag").cloned().unwrap_or_else(|| "latest".into());
+    if image.starts_with("missing/") {
+        return error(
+            StatusCode::NOT_FOUND,
+            &format!("pull access denied for {image}, repository does not exist"),
+        );
+    }
+
+    state.images.insert(format!("{image}:{tag}"));
+    let progress = format!(
+        "{}\n{}\n",
+        json!({ "status": format!("Pulling from {image}") }),
+        json!({ "status": format!("Downloaded newer image for {image}:{tag}") })
+    );
+    full(StatusCode::OK, progress.into_bytes())
+}
+
+/// Streams events published after the subscription for as long as the client listens.
+fn events(state: &State) -> Response<Body> {
+    let mut events = state.events.subscribe();
+    let (tx, rx) = tokio::sync::mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Ok(event) = events.recv().await {
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    let stream =
+        ReceiverStream::new(rx).map(|event| Ok::<_, Infallible>(Frame::data(Bytes::from(event))));
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(BoxBody::new(StreamBody::new(stream)))
+        .unwrap()
+}
+
+fn is_version(segment: &str) -> bool {
+    segment
+        .strip_prefix('v')
+        .is_some_and(|v| v.split('.').all(|part| part.parse::<u32>().is_ok()))
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.to_owned(),
+                percent_decode_str(value).decode_utf8_lossy().into_owned(),
+            )
+        })
+        .collect()
+}
+
+fn json_field(body: &Bytes, field: &str) -> String {
+    serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|v| v[field].as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
+
+fn full(status: StatusCode, body: Vec<u8>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(BoxBody::new(Full::from(body)))
+        .unwrap()
+}
+
+fn empty(status: StatusCode) -> Response<Body> {
+    full(status, Vec::new())
+}
+
+fn json_response(status: StatusCode, value: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(BoxBody::new(Full::from(value.to_string())))
+        .unwrap()
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, json!({ "message": message }))
+}
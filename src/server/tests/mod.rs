@@ -0,0 +1,131 @@
+//! Runs the gRPC services against [`mock_docker::MockDocker`].
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tonic::transport::{Channel, Server};
+
+use crate::config::Config;
+use crate::proto::{
+    compose_client::ComposeClient, docker_client::DockerClient, jobs_client::JobsClient,
+    scheduler_client::SchedulerClient, secrets_client::SecretsClient,
+};
+use crate::secret::SecretStore;
+use crate::service;
+
+mod compose;
+mod docker;
+mod history;
+mod info;
+mod jobs;
+mod mock_docker;
+mod schedule;
+mod secrets;
+mod stack;
+mod template;
+
+use mock_docker::MockDocker;
+
+/// A server with the compose, docker, jobs, secrets and scheduler services, talking to a
+/// fake daemon.
+struct Harness {
+    docker: MockDocker,
+    compose_dir: TempDir,
+    address: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let docker = MockDocker::start();
+        let compose_dir = tempfile::tempdir().unwrap();
+        let config = config(compose_dir.path());
+        let secret_store = Arc::new(Mutex::new(SecretStore::open(&config.secrets_path).unwrap()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            let stream = listener.accept().await.map(|(stream, _)| stream);
+            Some((stream, listener))
+        });
+
+        let router = Server::builder()
+            .add_service(service::docker(config.clone(), docker.client()))
+            .add_service(service::jobs(config.clone(), docker.client()))
+            .add_service(service::secrets(Arc::clone(&secret_store)))
+            .add_service(
+                service::scheduler(config.clone(), docker.client(), Arc::clone(&secret_store))
+                    .unwrap(),
+            )
+            .add_service(service::compose(config, docker.client(), secret_store));
+        let server = tokio::spawn(async move {
+            router.serve_with_incoming(incoming).await.unwrap();
+        });
+
+        Self {
+            docker,
+            compose_dir,
+            address,
+            server,
+        }
+    }
+
+    fn write_definition(&self, path: &str, content: &str) {
+        std::fs::write(self.compose_dir.path().join(path), content).unwrap();
+    }
+
+    async fn channel(&self) -> Channel {
+        Channel::from_shared(format!("http://{}", self.address))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap()
+    }
+
+    async fn docker_client(&self) -> DockerClient<Channel> {
+        DockerClient::new(self.channel().await)
+    }
+
+    async fn compose_client(&self) -> ComposeClient<Channel> {
+        ComposeClient::new(self.channel().await)
+    }
+
+    async fn jobs_client(&self) -> JobsClient<Channel> {
+        JobsClient::new(self.channel().await)
+    }
+
+    async fn secrets_client(&self) -> SecretsClient<Channel> {
+        SecretsClient::new(self.channel().await)
+    }
+
+    async fn scheduler_client(&self) -> SchedulerClient<Channel> {
+        SchedulerClient::new(self.channel().await)
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+fn config(compose_dir: &Path) -> Config {
+    toml::from_str(&format!(
+        r#"
+        address = "127.0.0.1:0"
+        docker_compose_path = {compose_dir:?}
+        update_interval = 1000
+        secrets_path = {secrets:?}
+        schedule_path = {schedule:?}
+        max_download_size = 1024
+        max_upload_size = 1024
+        "#,
+        secrets = compose_dir.join(".secrets"),
+        schedule = compose_dir.join(".schedule.json"),
+    ))
+    .unwrap()
+}
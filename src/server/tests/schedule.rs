@@ -0,0 +1,57 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::proto::DeployRequest;
+use crate::schedule::{Schedule, ScheduleStore};
+
+fn request() -> DeployRequest {
+    DeployRequest {
+        path: "web.toml".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn overdue_runs_are_recorded_as_missed() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = ScheduleStore::open(dir.path().join("schedule.json")).unwrap();
+    let at = Utc::now() + Duration::hours(1);
+    let once = store
+        .add(request(), Schedule::Once(at.timestamp()))
+        .unwrap();
+    let cron = store
+        .add(request(), Schedule::Cron("0 3 * * *".to_owned()))
+        .unwrap();
+    let cron_run = DateTime::from_timestamp(cron.next_run.unwrap(), 0).unwrap();
+
+    let due = store.take_due(cron_run + Duration::hours(2)).unwrap();
+
+    assert!(due.is_empty());
+    for deploy in store.deploys() {
+        let outcome = deploy.outcomes.last().unwrap();
+        assert!(!outcome.success);
+        assert_eq!(outcome.message, "missed");
+    }
+    let once = store.deploys().iter().find(|d| d.id == once.id).unwrap();
+    assert_eq!(once.next_run, None);
+    let cron = store.deploys().iter().find(|d| d.id == cron.id).unwrap();
+    assert_eq!(
+        cron.next_run,
+        Some((cron_run + Duration::days(1)).timestamp())
+    );
+}
+
+#[test]
+fn slightly_late_runs_still_happen() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = ScheduleStore::open(dir.path().join("schedule.json")).unwrap();
+    let at = Utc::now() + Duration::hours(1);
+    let once = store
+        .add(request(), Schedule::Once(at.timestamp()))
+        .unwrap();
+
+    let due = store.take_due(at + Duration::minutes(1)).unwrap();
+
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, once.id);
+    assert!(store.deploys()[0].outcomes.is_empty());
+}
@@ -0,0 +1,66 @@
+use tonic::Code;
+
+use crate::proto::{
+    schedule_deploy_request::Schedule, DeployRequest, ScheduleDeployRequest,
+    ScheduledDeployIdentifier, SecretName, SetSecretRequest,
+};
+
+use super::Harness;
+
+#[tokio::test]
+async fn secret_errors_map_to_status_codes() {
+    let harness = Harness::start().await;
+    let mut client = harness.secrets_client().await;
+
+    let status = client
+        .delete_secret(SecretName {
+            name: "missing".to_owned(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = client
+        .set_secret(SetSecretRequest {
+            name: "no spaces".to_owned(),
+            value: "value".to_owned(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn schedule_errors_map_to_status_codes() {
+    let harness = Harness::start().await;
+    let mut client = harness.scheduler_client().await;
+
+    let status = client
+        .cancel_scheduled(ScheduledDeployIdentifier {
+            id: "missing".to_owned(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = client
+        .schedule_deploy(ScheduleDeployRequest {
+            request: Some(DeployRequest {
+                path: "web.toml".to_owned(),
+                ..Default::default()
+            }),
+            schedule: Some(Schedule::At(0)),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = client
+        .schedule_deploy(ScheduleDeployRequest {
+            request: None,
+            schedule: Some(Schedule::Cron("0 3 * * *".to_owned())),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
@@ -0,0 +1,67 @@
+use crate::compose::stack::StackDefinition;
+
+fn stack(services: &str) -> StackDefinition {
+    toml::from_str(services).unwrap()
+}
+
+fn service(name: &str, depends_on: &[&str]) -> String {
+    format!(
+        r#"
+        [services.{name}]
+        image = "{name}"
+        tag = "1"
+        container_name = "{name}"
+        ports = []
+        depends_on = {depends_on:?}
+        "#
+    )
+}
+
+#[test]
+fn dependencies_come_first() {
+    let stack = stack(
+        &[
+            service("app", &["cache", "db"]),
+            service("cache", &[]),
+            service("db", &["volume"]),
+            service("proxy", &["app"]),
+            service("volume", &[]),
+        ]
+        .concat(),
+    );
+
+    let order = stack.deploy_order().unwrap();
+
+    assert_eq!(order, vec!["cache", "volume", "db", "app", "proxy"]);
+}
+
+#[test]
+fn cycles_are_rejected() {
+    let stack = stack(
+        &[
+            service("a", &["b"]),
+            service("b", &["c"]),
+            service("c", &["a"]),
+        ]
+        .concat(),
+    );
+
+    let err = stack.deploy_order().unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "dependency cycle between services: a -> b -> c -> a"
+    );
+}
+
+#[test]
+fn unknown_dependencies_are_rejected() {
+    let stack = stack(&[service("app", &["db"])].concat());
+
+    let err = stack.deploy_order().unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "service 'app' depends on unknown service 'db'"
+    );
+}
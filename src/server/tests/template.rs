@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::compose::{template, Definition};
+
+fn variables(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn substitute(content: &str, variables: &HashMap<String, String>) -> anyhow::Result<toml::Table> {
+    let mut table = toml::from_str(content).unwrap();
+    template::substitute(&mut table, variables, Path::new("web.toml"))?;
+    Ok(table)
+}
+
+#[test]
+fn substitutes_variables_defaults_and_escapes() {
+    let table = substitute(
+        r#"
+        image = "${REGISTRY}/web"
+        tag = "${TAG:-latest}"
+        command = "${EMPTY:-fallback} costs $$5 and $HOME stays"
+        ports = [{ host_port = "${PORT}" }]
+        "#,
+        &variables(&[
+            ("REGISTRY", "registry.local"),
+            ("PORT", "8080"),
+            ("EMPTY", ""),
+        ]),
+    )
+    .unwrap();
+
+    assert_eq!(table["image"].as_str(), Some("registry.local/web"));
+    assert_eq!(table["tag"].as_str(), Some("latest"));
+    assert_eq!(
+        table["command"].as_str(),
+        Some("fallback costs $5 and $HOME stays")
+    );
+    assert_eq!(table["ports"][0]["host_port"].as_str(), Some("8080"));
+}
+
+#[test]
+fn reports_all_unresolved_variables() {
+    let err = substitute(
+        r#"
+        image = "${IMAGE}"
+        tag = "${TAG}"
+        env = { A = "${IMAGE}" }
+        "#,
+        &HashMap::new(),
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().contains("unresolved variables IMAGE, TAG"));
+}
+
+#[test]
+fn rejects_unterminated_variables() {
+    let err = substitute(r#"image = "${IMAGE""#, &variables(&[("IMAGE", "web")])).unwrap_err();
+
+    assert!(err.to_string().contains("unterminated variable"));
+}
+
+#[test]
+fn ignores_comments_and_keeps_values_verbatim() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("web.toml");
+    std::fs::write(
+        &path,
+        r#"
+        # Set ${UNUSED} to override the password.
+        image = "nginx"
+        tag = "1.27"
+        container_name = "web"
+        ports = []
+        env = { PASSWORD = "${PASSWORD}" }
+        "#,
+    )
+    .unwrap();
+    let password = "a\"b\\c\nbinds = [\"/:/host\"]";
+
+    let Definition::Service(service) =
+        Definition::load(&path, &variables(&[("PASSWORD", password)])).unwrap()
+    else {
+        panic!("expected a service definition");
+    };
+
+    assert_eq!(service.env.unwrap()["PASSWORD"], password);
+    assert!(service.binds.is_none());
+}
+
+#[test]
+fn reads_env_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(".env");
+    std::fs::write(
+        &path,
+        "# comment\n\nTAG=1.27\nexport REGISTRY = registry.local\nQUOTED=\"a b\"\nSINGLE='c=d'\n",
+    )
+    .unwrap();
+
+    let variables = template::load_env_file(&path).unwrap();
+
+    assert_eq!(variables.len(), 4);
+    assert_eq!(variables["TAG"], "1.27");
+    assert_eq!(variables["REGISTRY"], "registry.local");
+    assert_eq!(variables["QUOTED"], "a b");
+    assert_eq!(variables["SINGLE"], "c=d");
+
+    assert!(template::load_env_file(&dir.path().join("missing"))
+        .unwrap()
+        .is_empty());
+
+    std::fs::write(&path, "TAG=1.27\nnot a variable\n").unwrap();
+    let err = template::load_env_file(&path).unwrap_err();
+    assert!(err.to_string().contains("invalid line 2"));
+}